@@ -5,7 +5,7 @@
 //! They are collected into a [CaptureList], which also includes information on
 //! any missing resources which cannot be made into [Capture]s.
 
-use crate::{display_systime, remote::Gotten};
+use crate::{display_systime, remote::Gotten, store::CaptureMeta};
 use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::path::PathBuf;
@@ -17,12 +17,26 @@ use url::Url;
 pub struct Capture {
     pub time: SystemTime,
     pub path: PathBuf,
-    pub url: Option<Url>, // upstream / remote URL, if any
+    pub url: Option<Url>,          // upstream / remote URL, if any
+    pub meta: Option<CaptureMeta>, // where it came from, if recorded
 }
 
 impl Capture {
     pub fn new(time: SystemTime, path: PathBuf, url: Option<Url>) -> Self {
-        Self { time, path, url }
+        let meta = None;
+        Self {
+            time,
+            path,
+            url,
+            meta,
+        }
+    }
+
+    /// with its recorded provenance, which gives its URL
+    pub fn with_meta(mut self, meta: CaptureMeta) -> Self {
+        self.url = Some(meta.url.clone());
+        self.meta = Some(meta);
+        self
     }
 
     pub fn valid(&self) -> bool {
@@ -74,6 +88,7 @@ impl From<(PathBuf, SystemTime)> for Capture {
             time: input.1,
             path: input.0,
             url: None,
+            meta: None,
         }
     }
 }
@@ -89,7 +104,13 @@ impl From<PathBuf> for Capture {
                 default
             }
         };
-        Self { time, path, url }
+        let meta = None;
+        Self {
+            time,
+            path,
+            url,
+            meta,
+        }
     }
 }
 
@@ -0,0 +1,179 @@
+//! Command line interface.
+
+use crate::config::{Config, ConfigArgsError, LoopCount};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "reflector",
+    version,
+    about = "Mirror satellite data from upstream sources"
+)]
+pub struct Cli {
+    /// configuration file, instead of $REFLECTOR_CONFIG or the XDG path
+    #[arg(short, long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// directory under which sources store captures
+    #[arg(long, global = true, value_name = "DIR")]
+    pub storage_root: Option<PathBuf>,
+
+    /// be more verbose
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// defaults to filling all sources
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// fetch missing captures within the loop period(s)
+    Fill(LoopArgs),
+    /// indicate how completely each source is mirrored
+    Status(SourceArgs),
+    /// display the captures within the loop period(s)
+    Captures(LoopArgs),
+    /// show the latest capture of each source
+    Latest(SourceArgs),
+    /// list the available sources
+    Sources,
+    /// verify each source can be set up as a mirror
+    Check(CheckArgs),
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct SourceArgs {
+    /// source names or abbreviations, all sources if none are given
+    #[arg(value_name = "SOURCE")]
+    pub names: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct LoopArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// number of loop periods to consider
+    #[arg(short, long, value_name = "N")]
+    pub loops: Option<u8>,
+}
+
+#[derive(Clone, Debug, Default, Args)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    pub sources: SourceArgs,
+
+    /// also ping each remote
+    #[arg(long)]
+    pub ping: bool,
+}
+
+impl Cli {
+    /// the subcommand to run, filling all sources if none was given
+    pub fn subcommand(&self) -> Command {
+        match &self.command {
+            Some(c) => c.clone(),
+            None => Command::Fill(LoopArgs::default()),
+        }
+    }
+
+    // override a loaded configuration with our arguments
+    fn apply(&self, mut c: Config) -> Result<Config, ConfigArgsError> {
+        if let Some(r) = &self.storage_root {
+            c.set_storage_root(r.clone());
+        }
+        let cmd = self.subcommand();
+        c.select(cmd.names())?;
+        if self.verbose {
+            c.verbose = true;
+        }
+        if let Some(l) = cmd.loops() {
+            c.loops = LoopCount::new(l);
+        }
+        Ok(c)
+    }
+}
+
+impl Command {
+    /// the sources named on the command line
+    pub fn names(&self) -> &[String] {
+        match self {
+            Command::Fill(a) | Command::Captures(a) => &a.sources.names,
+            Command::Status(a) | Command::Latest(a) => &a.names,
+            Command::Check(a) => &a.sources.names,
+            Command::Sources => &[],
+        }
+    }
+
+    pub fn loops(&self) -> Option<u8> {
+        match self {
+            Command::Fill(a) | Command::Captures(a) => a.loops,
+            _ => None,
+        }
+    }
+}
+
+/// load the configuration and narrow it to the sources named
+impl TryFrom<&Cli> for Config {
+    type Error = ConfigArgsError;
+
+    fn try_from(cli: &Cli) -> Result<Self, Self::Error> {
+        match Config::load(cli.config.as_deref()) {
+            Ok(c) => cli.apply(c),
+            Err(e) => Err(ConfigArgsError::ConfigFile(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn subcommands() {
+        let cli = Cli::parse_from(["reflector", "fill", "--loops", "3", "sdo"]);
+        let c = cli.apply(Config::default()).unwrap();
+        assert_eq!(1, c.sources.len());
+        assert_eq!(3, u32::from(&c.loops));
+
+        let cli = Cli::parse_from(["reflector", "status", "--storage-root", "/srv", "sdo"]);
+        let c = cli.apply(Config::default()).unwrap();
+        let sdo = c.sources.inner().pop().unwrap();
+        assert_eq!(Some(PathBuf::from("/srv/sdo")), sdo.local_path());
+
+        let path = std::env::temp_dir().join("reflector_cli_test.toml");
+        std::fs::write(&path, "storage_root = \"/data\"\n").unwrap();
+        let cli = Cli::parse_from(["reflector", "-c", path.to_str().unwrap(), "status", "sdo"]);
+        let c = Config::try_from(&cli).unwrap();
+        let sdo = c.sources.inner().pop().unwrap();
+        assert_eq!(Some(PathBuf::from("/data/sdo")), sdo.local_path());
+
+        let cli = Cli::parse_from(["reflector", "-v", "status"]);
+        assert!(matches!(cli.subcommand(), Command::Status(_)));
+        assert!(cli.verbose);
+
+        let cli = Cli::parse_from(["reflector"]);
+        assert!(
+            matches!(cli.subcommand(), Command::Fill(_)),
+            "fill by default"
+        );
+    }
+
+    #[test]
+    fn unknown_source() {
+        let cli = Cli::parse_from(["reflector", "captures", "nosuchsource"]);
+        assert!(matches!(
+            cli.apply(Config::default()),
+            Err(ConfigArgsError::UnknownSource(_))
+        ));
+    }
+}
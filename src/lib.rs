@@ -1,7 +1,12 @@
 pub mod config;
-pub use config::{Config, LoopCount, SourceConfig, SourceSearchError};
+pub use config::{
+    Config, ConfigArgsError, ConfigFileError, Discovery, LoopCount, Refresh, SourceConfig,
+    SourceSearchError,
+};
+pub mod cli;
+pub use cli::Cli;
 pub mod mirror;
-pub use mirror::{Mirror, MirrorError, MirrorStatus, StatusError};
+pub use mirror::{DiscoveryReport, Mirror, MirrorError, MirrorStatus, RefreshReport, StatusError};
 pub mod capture;
 pub use capture::{Capture, CaptureError, CaptureList, CaptureMissing};
 pub mod time_util;
@@ -13,8 +18,10 @@ pub use time_range::{StandardTimeRange, TimeRange, TimeRangeError};
 pub mod time_list;
 pub use time_list::TimeList;
 pub mod pathmaker;
-pub use pathmaker::{PathMaker, PathMakerError};
+pub use pathmaker::{PathMaker, PathMakerConfig, PathMakerError};
+pub mod media;
+pub use media::MediaType;
 pub mod store;
-pub use store::{FileList, FileStore, StoreError, StoreGetError};
+pub use store::{CaptureMeta, FileList, FileStore, StoreError, StoreGetError, Validity};
 pub mod remote;
-pub use remote::{GetError, RCFactoryError, RemoteClient};
+pub use remote::{GetError, RCFactoryError, RemoteClient, RemoteOptions};
@@ -1,81 +1,233 @@
+use clap::Parser;
 use log::{debug, info};
+use reflector::cli::{CheckArgs, Command};
+use reflector::remote::{redacted, ListError};
 use reflector::{
-    display_systime, CaptureList, Config, GetError, Mirror, MirrorStatus, StatusError,
+    display_systime, CaptureList, CaptureMissing, Cli, Config, Discovery, DiscoveryReport,
+    GetError, Mirror, MirrorStatus, StatusError,
 };
-use std::env;
+use std::fmt;
+use std::process::exit;
 
-// why ?
-#[allow(dead_code)]
 #[derive(Debug)]
 enum GetMirrorError {
     Unimplemented,
     RealStatusError(StatusError),
     RealGetError(GetError),
+    RealListError(ListError),
 }
 use GetMirrorError::*;
 
+impl fmt::Display for GetMirrorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unimplemented => write!(f, "status not implemented"),
+            RealStatusError(e) => write!(f, "status: {e:?}"),
+            RealGetError(e) => write!(f, "{e:?}"),
+            RealListError(e) => write!(f, "listing: {e:?}"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct GetMirrorResult {
     captures: Option<CaptureList>,
 }
 
-fn get_mirror(mut m: Mirror) -> Result<GetMirrorResult, GetMirrorError> {
+fn get_mirror(cfg: &Config, mut m: Mirror) -> Result<GetMirrorResult, GetMirrorError> {
+    // the range to fill, which is more than the status covers when
+    // looping more than once
+    let per = match m.loop_period_timerange(&cfg.loops) {
+        Ok(per) => per,
+        Err(e) => return Err(RealStatusError(StatusError::RangeError(e))),
+    };
     match m.status() {
-        Ok(s) => {
-            let do_get = match s {
-                MirrorStatus::Empty(_) => true,
-                MirrorStatus::Partial(_) => true,
-                MirrorStatus::Full(_) => false,
-                MirrorStatus::Unimplemented => return Err(Unimplemented),
-            };
-            if do_get {
-                println!("fetching mirror {}", m.name);
-                match m.fill_loop() {
-                    Ok(l) => Ok(GetMirrorResult { captures: Some(l) }),
-                    Err(e) => Err(RealGetError(e)),
-                }
-            } else {
+        Ok(MirrorStatus::Unimplemented) => return Err(Unimplemented),
+        Ok(MirrorStatus::Unreachable(_)) => {
+            println!("mirror {}: remote unreachable, not fetching", m.name);
+            return Ok(GetMirrorResult {
+                captures: Some(m.captures_in_range(&per)),
+            });
+        }
+        Ok(_) => (),
+        Err(e) => return Err(RealStatusError(e)),
+    }
+    if let Some(refresh) = m.refresh_range() {
+        let r = m.refresh_captures(&refresh);
+        println!("mirror {}: {r}", m.name);
+    }
+    let have = m.captures_in_range(&per);
+    if have.missing.is_empty() {
+        println!(
+            "mirror {} is already full for {} loop period(s)",
+            m.name, cfg.loops
+        );
+        return Ok(GetMirrorResult {
+            captures: Some(have),
+        });
+    }
+    println!("fetching mirror {}", m.name);
+    let cc = match m.discovery {
+        Discovery::Predicted => have,
+        Discovery::Listing => match m.discover(&per) {
+            Ok(r) => {
+                report(&r);
+                r.captures
+            }
+            Err(e) => return Err(RealListError(e)),
+        },
+    };
+    match m.fill_captures(cc) {
+        Ok(l) => Ok(GetMirrorResult { captures: Some(l) }),
+        Err(e) => Err(RealGetError(e)),
+    }
+}
+
+fn report(r: &DiscoveryReport) {
+    println!("upstream has {r}");
+    let show = |what: &str, mm: &[CaptureMissing]| {
+        for m in mm {
+            println!("\t{what}:\t{}\t{}", display_systime(&m.time), m.resource);
+        }
+    };
+    show("absent", &r.absent);
+    show("unpredicted", &r.unpredicted);
+}
+
+fn fill(cfg: &Config, m: Mirror) {
+    match get_mirror(cfg, m) {
+        Ok(r) if r.captures.is_none() => {
+            println!("no captures in our loop period");
+        }
+        Ok(r) => {
+            let cap = r.captures.unwrap();
+            println!("mirror has {}", cap);
+            if let Some(l) = cap.latest() {
                 println!(
-                    "mirror {} is already full for the default loop period",
-                    m.name
+                    "latest stamped {} file {}",
+                    display_systime(&l.time),
+                    l.path.display(),
                 );
-                Ok(GetMirrorResult {
-                    captures: Some(m.loop_captures()),
-                })
             }
         }
-        Err(e) => Err(RealStatusError(e)),
+        Err(e) => {
+            eprintln!("filling loop captures failed: {e}");
+        }
+    }
+}
+
+fn status(mut m: Mirror) {
+    match m.status() {
+        Ok(st) => {
+            println!("{}: {}\t{}", m.abbrev, m.name, m.local);
+            println!("\tstatus:\t{}", st);
+        }
+        Err(e) => eprintln!("{} status error: {:?}", m.name, e),
+    }
+}
+
+fn captures(cfg: &Config, m: Mirror) {
+    match m.loop_period_timerange(&cfg.loops) {
+        Ok(per) => {
+            let cc = m.captures_in_range(&per);
+            println!("{} captures in {} periods:", cc.len(), cfg.loops);
+            for c in cc {
+                println!("{c}");
+            }
+        }
+        Err(e) => eprintln!("error time range for {} loop: {:?}", cfg.loops, e),
+    }
+}
+
+// returns false if there is no latest capture
+fn latest(m: Mirror) -> bool {
+    match m.latest_capture() {
+        Some(c) => {
+            println!(
+                "{}: {}\t{}",
+                m.abbrev,
+                display_systime(&c.time),
+                c.path.display()
+            );
+            true
+        }
+        None => {
+            println!("{}: no captures in the loop period", m.abbrev);
+            false
+        }
+    }
+}
+
+// returns false if the check fails
+fn check(args: &CheckArgs, mut m: Mirror) -> bool {
+    if args.ping {
+        if let Err(e) = m.ping() {
+            println!(
+                "{}: cannot ping {}: {:?}",
+                m.abbrev,
+                redacted(m.remote.as_str()),
+                e
+            );
+            return false;
+        }
+    }
+    println!("{}: ok", m.abbrev);
+    true
+}
+
+fn sources(cfg: &Config) {
+    for src in cfg.sources.inner() {
+        println!(
+            "{}: {}\t{} -> {}",
+            src.abbrev,
+            src.name,
+            redacted(&src.remote),
+            match src.local_path() {
+                Some(l) => l.display().to_string(),
+                None => "?".to_string(),
+            }
+        );
     }
 }
 
 fn main() {
     std_logger::Config::logfmt().init();
-    let cfg = Config::try_from(env::args()).expect("error with args");
-    for src in cfg.sources {
+    let cli = Cli::parse();
+    let cfg = match Config::try_from(&cli) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("configuration error: {:?}", e);
+            exit(2);
+        }
+    };
+    let cmd = cli.subcommand();
+    if let Command::Sources = cmd {
+        sources(&cfg);
+        return;
+    }
+
+    let mut ok = true;
+    for src in cfg.sources.inner() {
         debug!("{:#?}", src);
-        match Mirror::new(src) {
-            Ok(m) => {
-                info!("got mirror {m}");
-                match get_mirror(m) {
-                    Ok(r) if r.captures.is_none() => {
-                        println!("no captures in our loop period");
-                    }
-                    Ok(r) => {
-                        let cap = r.captures.unwrap();
-                        println!("mirror has {}", cap);
-                        let l = cap.latest().unwrap();
-                        println!(
-                            "latest stamped {} file {}",
-                            display_systime(&l.time),
-                            l.path.display(),
-                        );
-                    }
-                    Err(e) => {
-                        eprintln!("filling loop captures failed: {:?}", e);
-                    }
-                }
+        let m = match Mirror::new(src.clone()) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("error with {src}: {:#?}", e);
+                ok = false;
+                continue;
             }
-            Err(e) => eprintln!("error: {:#?}", e),
+        };
+        info!("got mirror {m}");
+        match &cmd {
+            Command::Fill(_) => fill(&cfg, m),
+            Command::Status(_) => status(m),
+            Command::Captures(_) => captures(&cfg, m),
+            Command::Latest(_) => ok &= latest(m),
+            Command::Check(a) => ok &= check(a, m),
+            Command::Sources => unreachable!(),
         }
     }
+    if !ok {
+        exit(1);
+    }
 }
@@ -0,0 +1,543 @@
+//! The kinds of media mirrored, told by their names and their first
+//! few bytes, and checked in full by walking their structure.
+
+use flate2::write::ZlibDecoder;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaType {
+    Png,
+    Jpeg,
+    Ogg,
+    Mp4,
+}
+use MediaType::*;
+
+// enough of the start of a file to tell them apart
+const SNIFF_LEN: usize = 12;
+
+impl MediaType {
+    /// the media type a file name suggests, by its extension
+    pub fn from_name(name: &str) -> Option<MediaType> {
+        let ext = Path::new(name).extension().and_then(OsStr::to_str)?;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Png),
+            "jpg" | "jpeg" => Some(Jpeg),
+            "ogg" | "ogv" | "oga" => Some(Ogg),
+            "mp4" | "m4v" => Some(Mp4),
+            _ => None,
+        }
+    }
+
+    /// the media type the start of a file shows, by its magic bytes
+    pub fn sniff(head: &[u8]) -> Option<MediaType> {
+        if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Png)
+        } else if head.starts_with(b"\xff\xd8\xff") {
+            Some(Jpeg)
+        } else if head.starts_with(b"OggS") {
+            Some(Ogg)
+        } else if head.len() >= 8 && &head[4..8] == b"ftyp" {
+            Some(Mp4)
+        } else {
+            None
+        }
+    }
+
+    /// the media type of a file's contents
+    pub fn sniff_file(path: &Path) -> io::Result<Option<MediaType>> {
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        File::open(path)?
+            .take(SNIFF_LEN as u64)
+            .read_to_end(&mut head)?;
+        Ok(MediaType::sniff(&head))
+    }
+
+    /// whether a Content-Type could be this media type; generic
+    /// binary types could be anything
+    pub fn allows(&self, content_type: &str) -> bool {
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+        let essence = essence.to_ascii_lowercase();
+        match essence.as_str() {
+            "" | "application/octet-stream" | "binary/octet-stream" => true,
+            t => match self {
+                Png => t == "image/png",
+                Jpeg => t == "image/jpeg" || t == "image/pjpeg",
+                Ogg => matches!(t, "application/ogg" | "audio/ogg" | "video/ogg"),
+                Mp4 => matches!(t, "video/mp4" | "audio/mp4" | "application/mp4"),
+            },
+        }
+    }
+}
+
+impl MediaType {
+    /// check the whole of a file of this type, as far as telling it's
+    /// complete and uncorrupted goes: a PNG's chunks are CRC checked
+    /// and its image data inflated to the size its header implies, a
+    /// JPEG's segments are followed to its end of image, an MP4's boxes
+    /// and an Ogg's pages are followed to the end of the file, and the
+    /// pages CRC checked; it's read through once, never held in memory
+    pub fn decode<R: BufRead>(&self, r: R) -> Result<(), String> {
+        let s = Stream { r, at: 0 };
+        match self {
+            Png => decode_png(s),
+            Jpeg => decode_jpeg(s),
+            Ogg => decode_ogg(s),
+            Mp4 => decode_mp4(s),
+        }
+    }
+}
+
+// a file being decoded, read through in order, noting how far
+struct Stream<R> {
+    r: R,
+    at: u64,
+}
+
+impl<R: BufRead> Stream<R> {
+    fn exact(&mut self, buf: &mut [u8]) -> Result<(), String> {
+        match self.r.read_exact(buf) {
+            Ok(_) => {
+                self.at += buf.len() as u64;
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(format!("truncated at {}", self.at))
+            }
+            Err(e) => Err(format!("unreadable at {}: {e}", self.at)),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let mut b = [0];
+        self.exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    fn at_end(&mut self) -> Result<bool, String> {
+        match self.r.fill_buf() {
+            Ok(b) => Ok(b.is_empty()),
+            Err(e) => Err(format!("unreadable at {}: {e}", self.at)),
+        }
+    }
+
+    // the next n bytes, a piece at a time
+    fn each(
+        &mut self,
+        mut n: u64,
+        mut f: impl FnMut(&[u8]) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut buf = [0; 8192];
+        while n > 0 {
+            let k = n.min(buf.len() as u64) as usize;
+            self.exact(&mut buf[..k])?;
+            f(&buf[..k])?;
+            n -= k as u64;
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, n: u64) -> Result<(), String> {
+        self.each(n, |_| Ok(()))
+    }
+
+    // everything left
+    fn rest(&mut self) -> Result<(), String> {
+        match io::copy(&mut self.r, &mut io::sink()) {
+            Ok(n) => {
+                self.at += n;
+                Ok(())
+            }
+            Err(e) => Err(format!("unreadable at {}: {e}", self.at)),
+        }
+    }
+}
+
+fn be32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+// counts what's written to it
+struct Count(u64);
+
+impl Write for Count {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn decode_png<R: BufRead>(mut s: Stream<R>) -> Result<(), String> {
+    let mut sig = [0; 8];
+    if s.exact(&mut sig).is_err() || &sig != b"\x89PNG\r\n\x1a\n" {
+        return Err("no PNG signature".to_string());
+    }
+    let mut header: Option<[u8; 13]> = None;
+    let mut z = ZlibDecoder::new(Count(0));
+    loop {
+        let mut head = [0; 8];
+        if s.exact(&mut head).is_err() {
+            return Err("truncated before IEND".to_string());
+        }
+        let (len, kind) = (be32(&head) as u64, [head[4], head[5], head[6], head[7]]);
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&kind);
+        let mut body = Vec::new();
+        let truncated = |_: String| "truncated chunk".to_string();
+        s.each(len, |b| {
+            crc.update(b);
+            match &kind {
+                b"IDAT" => z.write_all(b).map_err(|e| format!("bad image data: {e}")),
+                b"IHDR" if len == 13 => {
+                    body.extend_from_slice(b);
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        })
+        .map_err(|e| match e.starts_with("truncated") {
+            true => truncated(e),
+            false => e,
+        })?;
+        let mut sum = [0; 4];
+        s.exact(&mut sum).map_err(truncated)?;
+        if crc.finalize() != be32(&sum) {
+            return Err(format!(
+                "bad CRC in {} chunk",
+                String::from_utf8_lossy(&kind)
+            ));
+        }
+        match &kind {
+            b"IHDR" if len == 13 => header = body.try_into().ok(),
+            b"IEND" => break,
+            _ => (),
+        }
+    }
+    let h = match header {
+        Some(h) => h,
+        None => return Err("no IHDR chunk".to_string()),
+    };
+    let (width, height) = (be32(&h) as u64, be32(&h[4..]) as u64);
+    if width == 0 || height == 0 || width > i32::MAX as u64 || height > i32::MAX as u64 {
+        return Err(format!("bad dimensions {width}x{height}"));
+    }
+    let channels = match (h[9], h[8]) {
+        (0, 1 | 2 | 4 | 8 | 16) | (3, 1 | 2 | 4 | 8) => 1,
+        (2, 8 | 16) => 3,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        (c, d) => return Err(format!("bad colour type {c} with bit depth {d}")),
+    };
+    let bits = channels * h[8] as u64;
+    // scanlines of each Adam7 pass, or the one pass, with a filter byte
+    let passes: &[(u64, u64, u64, u64)] = match h[12] {
+        0 => &[(0, 0, 1, 1)],
+        1 => &[
+            (0, 0, 8, 8),
+            (4, 0, 8, 8),
+            (0, 4, 4, 8),
+            (2, 0, 4, 4),
+            (0, 2, 2, 4),
+            (1, 0, 2, 2),
+            (0, 1, 1, 2),
+        ],
+        i => return Err(format!("unknown interlace method {i}")),
+    };
+    let mut expected: u64 = 0;
+    for &(x0, y0, dx, dy) in passes {
+        let w = width.saturating_sub(x0).div_ceil(dx);
+        let h = height.saturating_sub(y0).div_ceil(dy);
+        if w == 0 || h == 0 {
+            continue;
+        }
+        let pass = w
+            .checked_mul(bits)
+            .map(|b| 1 + b.div_ceil(8))
+            .and_then(|row| row.checked_mul(h))
+            .and_then(|n| n.checked_add(expected));
+        expected = match pass {
+            Some(n) => n,
+            None => return Err("image too large".to_string()),
+        };
+    }
+    let inflated = match z.finish() {
+        Ok(Count(n)) => n,
+        Err(e) => return Err(format!("bad image data: {e}")),
+    };
+    match inflated == expected {
+        true => Ok(()),
+        false => Err(format!("{inflated} bytes of image data, not {expected}")),
+    }
+}
+
+fn decode_jpeg<R: BufRead>(mut s: Stream<R>) -> Result<(), String> {
+    let mut soi = [0; 2];
+    if s.exact(&mut soi).is_err() || soi != [0xff, 0xd8] {
+        return Err("no JPEG start of image".to_string());
+    }
+    let truncated = |_: String| "truncated before end of image".to_string();
+    let mut frame = false;
+    let mut next: Option<u8> = None; // found after entropy coded data
+    loop {
+        let mut marker = match next.take() {
+            Some(m) => m,
+            None => {
+                let at = s.at;
+                match s.byte().map_err(truncated)? {
+                    0xff => s.byte().map_err(truncated)?,
+                    _ => return Err(format!("no marker at {at}")),
+                }
+            }
+        };
+        while marker == 0xff {
+            marker = s.byte().map_err(truncated)?; // fill
+        }
+        match marker {
+            0xd9 if frame => return Ok(()),
+            0xd9 => return Err("no frame before end of image".to_string()),
+            0x01 | 0xd0..=0xd7 => (),
+            _ => {
+                let mut len = [0; 2];
+                s.exact(&mut len).map_err(truncated)?;
+                let len = u16::from_be_bytes(len) as u64;
+                if len < 2 {
+                    return Err(format!("bad segment length at {}", s.at - 2));
+                }
+                s.skip(len - 2).map_err(truncated)?;
+                if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                    frame = true;
+                }
+                if marker == 0xda {
+                    // entropy coded data, to the next marker other than
+                    // a stuffed byte or a restart
+                    while next.is_none() {
+                        if s.byte().map_err(truncated)? != 0xff {
+                            continue;
+                        }
+                        match s.byte().map_err(truncated)? {
+                            0x00 | 0xd0..=0xd7 => (),
+                            m => next = Some(m),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn decode_mp4<R: BufRead>(mut s: Stream<R>) -> Result<(), String> {
+    let mut movie = false;
+    while !s.at_end()? {
+        let i = s.at;
+        let truncated = |_: String| format!("truncated box header at {i}");
+        let mut head = [0; 8];
+        s.exact(&mut head).map_err(truncated)?;
+        let kind = String::from_utf8_lossy(&head[4..]).to_string();
+        if i == 0 && kind != "ftyp" {
+            return Err("no ftyp box".to_string());
+        }
+        movie |= kind == "moov";
+        let size = match be32(&head) as u64 {
+            0 => {
+                s.rest()?; // to the end
+                break;
+            }
+            1 => {
+                let mut large = [0; 8];
+                s.exact(&mut large).map_err(truncated)?;
+                u64::from_be_bytes(large)
+            }
+            n => n,
+        };
+        let overrun = |_: String| format!("{kind} box at {i} overruns the file");
+        match size.checked_sub(s.at - i) {
+            Some(body) => s.skip(body).map_err(overrun)?,
+            None => return Err(overrun(String::new())),
+        }
+    }
+    match movie {
+        true => Ok(()),
+        false => Err("no moov box".to_string()),
+    }
+}
+
+// the CRC of Ogg pages: polynomial 0x04c11db7, unreflected, from 0,
+// continuing from that of what came before
+fn ogg_crc(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= (b as u32) << 24;
+        for _ in 0..8 {
+            crc = match crc & 0x8000_0000 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x04c1_1db7,
+            };
+        }
+    }
+    crc
+}
+
+fn decode_ogg<R: BufRead>(mut s: Stream<R>) -> Result<(), String> {
+    if s.at_end()? {
+        return Err("empty".to_string());
+    }
+    while !s.at_end()? {
+        let i = s.at;
+        let mut head = [0; 27];
+        if s.exact(&mut head).is_err() || !head.starts_with(b"OggS") {
+            return Err(format!("no page at {i}"));
+        }
+        let mut table = vec![0; head[26] as usize];
+        if s.exact(&mut table).is_err() {
+            return Err(format!("truncated page header at {i}"));
+        }
+        let sum = u32::from_le_bytes(head[22..26].try_into().unwrap());
+        head[22..26].fill(0);
+        let mut crc = ogg_crc(ogg_crc(0, &head), &table);
+        let body: u64 = table.iter().map(|&n| n as u64).sum();
+        s.each(body, |b| {
+            crc = ogg_crc(crc, b);
+            Ok(())
+        })
+        .map_err(|_| format!("truncated page at {i}"))?;
+        if crc != sum {
+            return Err(format!("bad CRC in page at {i}"));
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Png => "PNG",
+            Jpeg => "JPEG",
+            Ogg => "Ogg",
+            Mp4 => "MP4",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Some(Png), MediaType::from_name("GOES/x_1500z.png"));
+        assert_eq!(Some(Jpeg), MediaType::from_name("img_2024060_2350.JPG"));
+        assert_eq!(Some(Mp4), MediaType::from_name("20231013_588_SDO_VO2.mp4"));
+        assert_eq!(Some(Ogg), MediaType::from_name("x.ogv"));
+        assert_eq!(None, MediaType::from_name("20231014_0700.txt"));
+        assert_eq!(None, MediaType::from_name("png"));
+    }
+
+    #[test]
+    fn sniff() {
+        assert_eq!(
+            Some(Png),
+            MediaType::sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")
+        );
+        assert_eq!(Some(Jpeg), MediaType::sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"));
+        assert_eq!(Some(Ogg), MediaType::sniff(b"OggS\0\x02"));
+        assert_eq!(Some(Mp4), MediaType::sniff(b"\0\0\0\x20ftypisom"));
+        assert_eq!(None, MediaType::sniff(b"<!DOCTYPE html>"));
+        assert_eq!(None, MediaType::sniff(b""));
+    }
+
+    #[test]
+    fn allows() {
+        assert!(Png.allows("image/png"));
+        assert!(Png.allows("application/octet-stream"));
+        assert!(Mp4.allows("video/mp4; charset=binary"));
+        assert!(!Png.allows("text/html; charset=utf-8"));
+        assert!(!Jpeg.allows("image/png"));
+    }
+
+    // a 2x2 RGB PNG, as written by an image library
+    fn png() -> Vec<u8> {
+        png_with(&[0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0])
+    }
+
+    // a 2x2 RGB image's data, under whatever header
+    fn png_with(ihdr: &[u8]) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut chunk = |kind: &[u8], body: &[u8]| {
+            let mut c = kind.to_vec();
+            c.extend_from_slice(body);
+            png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            png.extend_from_slice(&c);
+            png.extend_from_slice(&crc32fast::hash(&c).to_be_bytes());
+        };
+        chunk(b"IHDR", ihdr);
+        let rows = [0u8, 1, 2, 3, 4, 5, 6, 0, 7, 8, 9, 10, 11, 12];
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut z, &rows).unwrap();
+        chunk(b"IDAT", &z.finish().unwrap());
+        chunk(b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn decode_png() {
+        let png = png();
+        Png.decode(png.as_slice()).unwrap();
+        assert!(Png.decode(&png[..png.len() - 12]).is_err(), "no IEND");
+        let mut bad = png.clone();
+        bad[40] ^= 1;
+        assert!(Png.decode(bad.as_slice()).is_err(), "bad CRC");
+
+        let huge = png_with(&[
+            0x7f, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 16, 6, 0, 0, 0,
+        ]);
+        assert_eq!("image too large", Png.decode(huge.as_slice()).unwrap_err());
+        let huge = png_with(&[
+            0x7f, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 16, 6, 0, 0, 1,
+        ]);
+        assert_eq!("image too large", Png.decode(huge.as_slice()).unwrap_err());
+        let odd = png_with(&[0, 0, 0, 2, 0, 0, 0, 2, 4, 2, 0, 0, 0]);
+        assert!(Png.decode(odd.as_slice()).is_err(), "4 bit RGB");
+        let odd = png_with(&[0, 0, 0, 2, 0, 0, 0, 2, 200, 6, 0, 0, 0]);
+        assert!(Png.decode(odd.as_slice()).is_err(), "200 bit RGBA");
+        let empty = png_with(&[0, 0, 0, 0, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert!(Png.decode(empty.as_slice()).is_err(), "no width");
+    }
+
+    #[test]
+    fn decode_jpeg() {
+        let jpeg =
+            b"\xff\xd8\xff\xe0\0\x04JF\xff\xc0\0\x03\x08\xff\xda\0\x02\x12\xff\0\x34\xff\xd9";
+        Jpeg.decode(&jpeg[..]).unwrap();
+        assert!(Jpeg.decode(&jpeg[..jpeg.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn decode_mp4() {
+        let mut mp4 = b"\0\0\0\x10ftypisom\0\0\0\0".to_vec();
+        mp4.extend_from_slice(b"\0\0\0\x0cmoov\0\0\0\0");
+        Mp4.decode(mp4.as_slice()).unwrap();
+        assert!(Mp4.decode(&mp4[..20]).is_err());
+        assert!(Mp4.decode(&mp4[..16]).is_err(), "no moov");
+    }
+
+    #[test]
+    fn decode_ogg() {
+        let mut ogg = b"OggS\0\x02".to_vec();
+        ogg.extend_from_slice(&[0; 20]);
+        ogg.extend_from_slice(&[1, 3, b'a', b'b', b'c']);
+        let crc = ogg_crc(0, &ogg);
+        ogg[22..26].copy_from_slice(&crc.to_le_bytes());
+        Ogg.decode(ogg.as_slice()).unwrap();
+        assert!(Ogg.decode(&ogg[..ogg.len() - 1]).is_err());
+        ogg[28] = b'x';
+        assert!(Ogg.decode(ogg.as_slice()).is_err(), "bad CRC");
+    }
+}
@@ -1,10 +1,17 @@
 //! Shadow upstream data to local storage.
 
 use crate::pathmaker;
-use crate::remote::{from_url as remote_from_url, Gotten, PingError};
+use crate::remote::{
+    from_url_with_options as remote_from_url, redacted, Gotten, ListError, PingError, RemoteStat,
+};
+use crate::store::meta::sha256_file;
 use crate::*;
-use log::{info, warn};
+use chrono_tz::Tz;
+use log::{debug, info, warn};
+use percent_encoding::percent_decode_str;
+use std::ffi::OsStr;
 use std::fmt;
+use std::fs;
 use std::path::PathBuf;
 use std::time::{self, Duration, SystemTime};
 use url::Url;
@@ -15,6 +22,7 @@ pub enum MirrorError {
     InvalidStore(StoreError),
     InvalidPathMaker(PathMakerError),
     InvalidRemote(RCFactoryError),
+    NoStorageRoot(String), // for a relative local path
 }
 use MirrorError::*;
 
@@ -26,6 +34,7 @@ pub enum MirrorStatus {
     Full(time::SystemTime),
     Partial(time::SystemTime),
     Empty(time::SystemTime),
+    Unreachable(Box<MirrorStatus>), // as far as the store shows
 }
 
 impl fmt::Display for MirrorStatus {
@@ -43,6 +52,7 @@ impl fmt::Display for MirrorStatus {
                     "mirror latest {}, only partially reflected",
                     display_systime(t)
                 ),
+                MirrorStatus::Unreachable(s) => format!("remote unreachable; {s}"),
             }
         )
     }
@@ -51,23 +61,72 @@ impl fmt::Display for MirrorStatus {
 #[derive(Debug)]
 pub enum StatusError {
     Unimplemented,
-    CannotPing(PingError),
     RangeError(time_range::TimeRangeError),
     CaptureError(CaptureError),
     Inconsistent, // shouldn't normally happen
 }
 
+/// what listing upstream found over a time range, against the
+/// filenames predicted for it
+#[derive(Clone, Debug)]
+pub struct DiscoveryReport {
+    /// the files listed, whether already captured or still missing
+    pub captures: CaptureList,
+    /// predicted but not listed
+    pub absent: Vec<CaptureMissing>,
+    /// listed but not predicted
+    pub unpredicted: Vec<CaptureMissing>,
+}
+
+impl fmt::Display for DiscoveryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} listed, {} predicted but absent, {} present but unpredicted",
+            self.captures.len_all(),
+            self.absent.len(),
+            self.unpredicted.len()
+        )
+    }
+}
+
+/// what checking captures against upstream found
+#[derive(Debug)]
+pub struct RefreshReport {
+    /// captures with a record of what upstream was when fetched
+    pub checked: usize,
+    /// fetched again, upstream having changed
+    pub replaced: Vec<Capture>,
+    /// couldn't be checked or fetched again, and are left as they were
+    pub failed: Vec<(Capture, GetError)>,
+}
+
+impl fmt::Display for RefreshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} checked, {} changed upstream and replaced, {} failed",
+            self.checked,
+            self.replaced.len(),
+            self.failed.len()
+        )
+    }
+}
+
 /// a remote site, kept in sync with a local file store
 pub struct Mirror {
     pub name: String,
     pub abbrev: String,
     pub period: time::Duration, // period between captures
     pub seed_past_midnight: time::Duration,
+    pub timezone: Tz, // in which seed_past_midnight is reckoned
     pub loop_period: time::Duration,
     pub local: FileStore,
     pub remote: Url,
     remote_client: Box<dyn RemoteClient>,
     pub flatten: bool,
+    pub discovery: Discovery,
+    pub refresh: Option<Refresh>,
     pub pathmaker: Box<dyn PathMaker>,
     pub sourceconfig: SourceConfig,
 }
@@ -87,22 +146,27 @@ impl Mirror {
             return Err(InvalidURL(e));
         }
         let remote = remote.unwrap();
-        // FIXME: requires network to be up
-        let remote_client = remote_from_url(&remote);
+        // clients connect when first used, so this works offline
+        let remote_client = remote_from_url(&remote, &cfg.remote_options);
         if let Err(e) = remote_client {
             return Err(InvalidRemote(e));
         }
         let remote_client = remote_client.unwrap();
 
         let p2 = pathmaker::new(&cfg.pathmaker).unwrap();
-        let local = FileStore::new(&cfg.local, p2);
+        let local_path = match cfg.local_path() {
+            Some(l) => l,
+            None => return Err(NoStorageRoot(cfg.abbrev)),
+        };
+        let local = FileStore::new(&local_path.to_string_lossy(), p2);
         if let Err(e) = local {
             return Err(InvalidStore(e));
         }
-        let local = local.unwrap();
+        let local = local.unwrap().with_validity(cfg.validity.clone());
 
         let flatten = matches!(cfg.flatten, Some(true));
         let seed_past_midnight = Duration::new(cfg.offset.unwrap_or(0), 0);
+        let timezone = cfg.timezone.unwrap_or(Tz::UTC);
         let loop_period = Duration::new(cfg.loop_period.unwrap_or(cfg.period), 0);
 
         let m = Mirror {
@@ -110,11 +174,14 @@ impl Mirror {
             abbrev: cfg.abbrev,
             period,
             seed_past_midnight,
+            timezone,
             local,
             remote,
             remote_client,
             pathmaker,
             flatten,
+            discovery: cfg.discovery,
+            refresh: cfg.refresh,
             loop_period,
             sourceconfig,
         };
@@ -141,11 +208,8 @@ impl Mirror {
         TimeRange::new(then, now)
     }
 
-    pub fn status(&mut self) -> Result<MirrorStatus, StatusError> {
-        if let Err(e) = self.remote_client.ping() {
-            return Err(StatusError::CannotPing(e));
-        }
-
+    /// the status of the mirror, from the store alone
+    pub fn store_status(&self) -> Result<MirrorStatus, StatusError> {
         // check the store for files within our range,
         // and set the status accordingly
         let cc = self.loop_captures();
@@ -169,6 +233,18 @@ impl Mirror {
         }
     }
 
+    /// the status of the mirror, noting if the remote can't be reached
+    pub fn status(&mut self) -> Result<MirrorStatus, StatusError> {
+        let status = self.store_status()?;
+        match self.remote_client.ping() {
+            Ok(_) => Ok(status),
+            Err(e) => {
+                warn!("cannot reach {}: {:?}", redacted(self.remote.as_str()), e);
+                Ok(MirrorStatus::Unreachable(Box::new(status)))
+            }
+        }
+    }
+
     pub fn ping(&mut self) -> Result<time::Duration, PingError> {
         self.remote_client.ping()
     }
@@ -178,10 +254,14 @@ impl Mirror {
         self.remote_client.url(rsrc)
     }
 
-    pub fn exists(&self, rsrc: &str) -> Result<bool, GetError> {
+    pub fn exists(&mut self, rsrc: &str) -> Result<bool, GetError> {
         self.remote_client.exists(rsrc)
     }
 
+    pub fn stat(&mut self, rsrc: &str) -> Result<RemoteStat, GetError> {
+        self.remote_client.stat(rsrc)
+    }
+
     pub fn get(&mut self, rsrc: &str, path: PathBuf) -> Result<Gotten, GetError> {
         self.remote_client.get(rsrc, path)
     }
@@ -192,9 +272,12 @@ impl Mirror {
     }
 
     pub fn timelist(&self, range: &TimeRange) -> TimeList {
-        range
-            .clone()
-            .make_timelist(&self.period, &self.seed_past_midnight)
+        TimeList::from((
+            range.clone(),
+            self.period,
+            self.seed_past_midnight,
+            self.timezone,
+        ))
     }
 
     fn filelist(&self, times: &TimeList) -> FileList {
@@ -216,27 +299,39 @@ impl Mirror {
         self.filelist(&times)
     }
 
+    // where the store keeps a remote file
+    fn local_path(&self, f: &OsStr) -> PathBuf {
+        match self.flatten {
+            true => PathBuf::from(flatten_filename(f)),
+            false => PathBuf::from(f),
+        }
+    }
+
+    // the capture of a remote file, if the store has it, else what's
+    // missing
+    fn capture_or_missing(&self, time: SystemTime, f: &OsStr) -> Result<Capture, CaptureMissing> {
+        let path = self.local_path(f);
+        match self.local.get(&path) {
+            Ok(cap) => Ok(cap),
+            Err(e) => {
+                match e {
+                    StoreGetError::NoSuchFile(_) => (),
+                    _ => eprintln!("unexpected error: {:?}", e),
+                }
+                Err(CaptureMissing::new(time, path, f.to_str().unwrap_or("")))
+            }
+        }
+    }
+
     // based on a TimeList, construct a capturelist reflecting which
     // files we've already downloaded
     pub fn captures_in_timelist(&self, times: TimeList) -> CaptureList {
         let mut c = CaptureList::empty();
         for time in times {
             let f = self.pathmaker.systime_to_filename(&time);
-            let path = match self.flatten {
-                true => flatten_filename(&f),
-                false => f.clone(),
-            };
-            let path = PathBuf::from(path);
-            match self.local.get(&path) {
+            match self.capture_or_missing(time, &f) {
                 Ok(cap) => c.push(cap),
-                Err(e) => {
-                    let cap = CaptureMissing::new(time, path, f.to_str().unwrap_or(""));
-                    c.push_missing(cap);
-                    match e {
-                        StoreGetError::NoSuchFile(_) => (),
-                        _ => eprintln!("unexpected error: {:?}", e),
-                    }
-                }
+                Err(m) => c.push_missing(m),
             }
         }
         c
@@ -247,6 +342,70 @@ impl Mirror {
         self.captures_in_timelist(times)
     }
 
+    /// the remote directories which would hold the files for a range,
+    /// as prefixes to list
+    pub fn listing_dirs(&self, range: &TimeRange) -> Vec<String> {
+        let mut dirs: Vec<String> = Vec::new();
+        let ends = [range.from, range.to];
+        for t in self.timelist(range).chain(ends) {
+            let f = self.pathmaker.systime_to_filename(&t);
+            let f = f.to_string_lossy();
+            let dir = match f.rfind('/') {
+                Some(i) => f[..=i].to_string(),
+                None => String::new(),
+            };
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
+    /// list upstream for the files within a range, as dated by the
+    /// pathmaker, comparing them with those predicted
+    pub fn discover(&mut self, range: &TimeRange) -> Result<DiscoveryReport, ListError> {
+        let mut listed: Vec<(SystemTime, String)> = Vec::new();
+        for dir in self.listing_dirs(range) {
+            for entry in self.remote_client.list(&dir)? {
+                match self.pathmaker.filename_to_systime(OsStr::new(&entry.name)) {
+                    Ok(t) if range.contains(t) => listed.push((t, entry.name)),
+                    Ok(_) => (),
+                    Err(e) => debug!("ignoring listed {}: {:?}", entry.name, e),
+                }
+            }
+        }
+        let predicted: Vec<(SystemTime, String)> = self
+            .timelist(range)
+            .map(|t| {
+                let f = self.pathmaker.systime_to_filename(&t);
+                (t, f.to_string_lossy().to_string())
+            })
+            .collect();
+
+        let mut report = DiscoveryReport {
+            captures: CaptureList::empty(),
+            absent: Vec::new(),
+            unpredicted: Vec::new(),
+        };
+        let missing =
+            |t: SystemTime, f: &str| CaptureMissing::new(t, self.local_path(f.as_ref()), f);
+        for (t, f) in predicted.iter() {
+            if !listed.iter().any(|(_, l)| l == f) {
+                report.absent.push(missing(*t, f));
+            }
+        }
+        for (t, l) in listed.iter() {
+            if !predicted.iter().any(|(_, f)| f == l) {
+                report.unpredicted.push(missing(*t, l));
+            }
+            match self.capture_or_missing(*t, l.as_ref()) {
+                Ok(cap) => report.captures.push(cap),
+                Err(m) => report.captures.push_missing(m),
+            }
+        }
+        Ok(report)
+    }
+
     pub fn all_capturess(&self) -> Result<CaptureList, StoreGetError> {
         self.local.all_captures()
     }
@@ -267,11 +426,15 @@ impl Mirror {
         let mut err: Option<GetError> = None;
         while let Some(m) = missing.pop_back() {
             info!("attempting to fill missing {}", m.resource);
-            match self.get_missing(&m) {
+            match self.get_missing(&m).and_then(validated) {
                 Ok(g) => {
-                    info!("success with {}", g.source);
+                    info!("success with {}", redacted(g.source.as_str()));
+                    let meta = self.local.record(&g);
                     let c = Capture::from((g, m.time));
-                    new.push(c)
+                    new.push(match meta {
+                        Some(meta) => c.with_meta(meta),
+                        None => c,
+                    })
                 }
                 Err(e) => {
                     warn!("error: {:?}", e);
@@ -295,14 +458,118 @@ impl Mirror {
         self.fill_captures(self.loop_captures())
     }
 
+    /// the recent range within which captures are checked against
+    /// upstream, if they are
+    pub fn refresh_range(&self) -> Option<TimeRange> {
+        let r = self.refresh.as_ref()?;
+        TimeRange::from_now_to(&Duration::from_secs(r.window)).ok()
+    }
+
+    // the resource upstream a URL names, relative to the remote base
+    fn resource_of(&self, url: &Url) -> Option<String> {
+        let base = self.remote_client.url("").ok()?;
+        let rel = base.make_relative(url)?;
+        Some(percent_decode_str(&rel).decode_utf8_lossy().to_string())
+    }
+
+    /// fetch again the captures within a range which upstream has
+    /// changed since, going by what was recorded when they were fetched
+    pub fn refresh_captures(&mut self, range: &TimeRange) -> RefreshReport {
+        let keep = matches!(&self.refresh, Some(r) if r.keep);
+        let mut report = RefreshReport {
+            checked: 0,
+            replaced: Vec::new(),
+            failed: Vec::new(),
+        };
+        // those predicted, and any others listing found
+        let mut cc: Vec<Capture> = self.captures_in_range(range).list.into();
+        if let Ok(all) = self.local.all_captures() {
+            for c in all.list {
+                if range.contains(c.time) && !cc.iter().any(|p| p.path == c.path) {
+                    cc.push(c);
+                }
+            }
+        }
+        for c in cc {
+            let (meta, resource) = match &c.meta {
+                Some(m) => match self.resource_of(&m.url) {
+                    Some(r) => (m.clone(), r),
+                    None => {
+                        debug!("{} isn't from {}", m.url, redacted(self.remote.as_str()));
+                        continue;
+                    }
+                },
+                None => continue,
+            };
+            report.checked += 1;
+            let name = c.path.file_name().unwrap_or_default().to_string_lossy();
+            let fresh = c.path.with_file_name(format!(".{name}.new"));
+            let _ = fs::remove_file(&fresh);
+            let got = self
+                .remote_client
+                .get_if_changed(&resource, fresh.clone(), &meta.fetched())
+                .and_then(|g| g.map(validated).transpose());
+            match got {
+                Ok(None) => debug!("{resource} unchanged"),
+                // as from a server which ignores conditions, and gives no
+                // ETag or Last-Modified to go by
+                Ok(Some(mut g)) if sha256_file(&g.output).is_ok_and(|h| h == meta.sha256) => {
+                    debug!("{resource} fetched again, but unchanged");
+                    let _ = fs::remove_file(&g.output);
+                    // recording what upstream now says of it
+                    g.output = c.path.clone();
+                    self.local.record(&g);
+                }
+                Ok(Some(g)) => match self.local.replace(&c, g, keep) {
+                    Ok(new) => {
+                        info!("replaced {} from changed {resource}", new.path.display());
+                        report.replaced.push(new);
+                    }
+                    Err(e) => {
+                        warn!("can't replace {}: {e}", c.path.display());
+                        let _ = fs::remove_file(&fresh);
+                        report.failed.push((c, GetError::LocalIOError(e)));
+                    }
+                },
+                Err(e) => {
+                    warn!("can't refresh {resource}: {:?}", e);
+                    report.failed.push((c, e));
+                }
+            }
+        }
+        report
+    }
+
     pub fn latest_capture(&self) -> Option<Capture> {
         self.loop_captures().last()
     }
 }
 
+// a Gotten which is all it should be, such as a PNG of the size
+// upstream gave; any other is removed, so as not to count as a capture
+fn validated(g: Gotten) -> Result<Gotten, GetError> {
+    match g.validate() {
+        Ok(_) => Ok(g),
+        Err(v) => {
+            warn!("rejecting {}: {:?}", g.output.display(), v);
+            if let Err(e) = fs::remove_file(&g.output) {
+                warn!("can't remove {}: {e}", g.output.display());
+            }
+            Err(GetError::InvalidOutput(v))
+        }
+    }
+}
+
 impl fmt::Display for Mirror {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "<mirror {}>", self.name)
+        write!(
+            f,
+            "<mirror {} p: {} o: {} lp: {}>",
+            self.name,
+            display_compact_duration(&self.period),
+            display_time_of_day(&self.seed_past_midnight),
+            display_compact_duration(&self.loop_period),
+        )
     }
 }
 
@@ -318,6 +585,7 @@ impl TryFrom<SourceConfig> for Mirror {
 mod tests {
     use super::*;
     use crate::time_util::*;
+    use chrono::{TimeZone, Utc};
     use httpmock;
     use std::env;
     use std::fs;
@@ -344,18 +612,24 @@ mod tests {
         let dt = datetime_from_systime(SystemTime::now());
         let ts: String = format!("{}", dt.format("%Y-%m-%dT00:00:00+00:00"));
         eprintln!("creating file in store '{}'...", fcp.join(&ts).display());
-        let _file = fs::File::create(fcp.join(&ts));
+        let _ = fs::write(fcp.join(&ts), "mock capture");
 
         SourceConfig {
             name: "mock mirror source".to_string(),
             abbrev: "mock".to_string(),
             remote: srv.base_url(),
-            local: fc.display().to_string(),
-            pathmaker: "identity".to_string(),
+            local: Some(fc.display().to_string()),
+            pathmaker: PathMakerConfig::new("identity"),
             flatten: None,
+            discovery: Discovery::Predicted,
             period: 60 * 60, // once per hour
             offset: None,
+            timezone: None,
             loop_period: Some(60 * 60 * 24), // 1 day
+            remote_options: RemoteOptions::default(),
+            validity: Validity::default(),
+            refresh: None,
+            storage_root: None,
         }
     }
 
@@ -363,6 +637,29 @@ mod tests {
         Mirror::new(mock_src_config()).unwrap()
     }
 
+    // a mirror of an emptied upstream directory, by file: URL, into an
+    // emptied store, with filenames of the given format
+    fn local_mirror(
+        name: &str,
+        format: &str,
+        tweak: impl FnOnce(&mut SourceConfig),
+    ) -> (PathBuf, PathBuf, Mirror) {
+        let upstream = env::temp_dir().join(format!("reflector-mirror-{name}-upstream"));
+        let store = env::temp_dir().join(format!("reflector-mirror-{name}-store"));
+        let _ = fs::remove_dir_all(&upstream);
+        let _ = fs::remove_dir_all(&store);
+        fs::create_dir_all(&upstream).unwrap();
+        fs::create_dir_all(&store).unwrap();
+
+        let mut cfg = mock_src_config();
+        cfg.remote = Url::from_directory_path(&upstream).unwrap().to_string();
+        cfg.local = Some(store.display().to_string());
+        cfg.pathmaker = PathMakerConfig::new("template").with("format", format);
+        cfg.flatten = Some(true);
+        tweak(&mut cfg);
+        (upstream, store, Mirror::new(cfg).unwrap())
+    }
+
     #[test]
     fn periods_and_ranges() {
         // setup a once a day, fortnightly looping sourceconfig
@@ -382,6 +679,266 @@ mod tests {
         );
     }
 
+    #[test]
+    fn registered_pathmaker() {
+        let mut cfg = mock_src_config();
+        cfg.pathmaker = PathMakerConfig::new("mirror-test-kind");
+        assert!(matches!(Mirror::new(cfg.clone()), Err(InvalidPathMaker(_))));
+        pathmaker::register("mirror-test-kind", |_| {
+            Ok(Box::new(pathmaker::Identity::new()))
+        });
+        Mirror::new(cfg).unwrap();
+    }
+
+    #[test]
+    fn registered_remote() {
+        let mut cfg = mock_src_config();
+        cfg.remote = "mirror-test://upstream/".to_string();
+        assert!(matches!(Mirror::new(cfg.clone()), Err(InvalidRemote(_))));
+        remote::register("mirror-test", |_, _| Ok(Box::new(remote::mock::Mock())));
+        let mut m = Mirror::new(cfg).unwrap();
+        m.ping().unwrap();
+    }
+
+    #[test]
+    fn local_fill() {
+        // an upstream directory with hourly files for a day
+        let (upstream, store, mut m) = local_mirror("local", "%Y/%m/%d/%Y%m%d_%H%M.txt", |_| ());
+        fs::create_dir_all(upstream.join("2023/10/14")).unwrap();
+        for h in 0..24 {
+            let f = upstream.join(format!("2023/10/14/20231014_{h:02}00.txt"));
+            fs::write(f, format!("hour {h}")).unwrap();
+        }
+        m.ping().unwrap();
+
+        let day = TimeRange::from((
+            Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 10, 14, 23, 59, 0).unwrap(),
+        ));
+        let cc = m.captures_in_range(&day);
+        assert_eq!(24, cc.missing.len());
+        let filled = m.fill_captures(cc).unwrap();
+        assert_eq!(24, filled.len());
+        assert!(filled.missing.is_empty());
+        assert_eq!(
+            "hour 7",
+            fs::read_to_string(store.join("20231014_0700.txt")).unwrap()
+        );
+        let cc = m.captures_in_range(&day);
+        assert!(cc.missing.is_empty());
+
+        // with where each came from
+        let c = cc
+            .list
+            .iter()
+            .find(|c| c.path.ends_with("20231014_0700.txt"));
+        let meta = c.unwrap().meta.clone().expect("a provenance record");
+        let source = upstream.join("2023/10/14/20231014_0700.txt");
+        assert_eq!(Url::from_file_path(&source).unwrap(), meta.url);
+        assert_eq!(Some(&meta.url), c.unwrap().url.as_ref());
+        assert_eq!(6, meta.size);
+        assert_eq!(
+            crate::store::meta::sha256_file(&source).unwrap(),
+            meta.sha256
+        );
+        let modified = fs::metadata(&source).unwrap().modified().unwrap();
+        assert_eq!(Some(chrono::DateTime::from(modified)), meta.remote_modified);
+    }
+
+    #[test]
+    fn invalid_fill() {
+        let (upstream, store, mut m) = local_mirror("invalid", "%Y%m%d_%H%M.png", |_| ());
+        fs::write(upstream.join("20231014_0000.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        fs::write(upstream.join("20231014_0100.png"), "<html>oops</html>").unwrap();
+        let hours = TimeRange::from((
+            Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 10, 14, 1, 0, 0).unwrap(),
+        ));
+        match m.fill_captures(m.captures_in_range(&hours)) {
+            Err(GetError::IncompleteFill(e, missing)) => {
+                assert!(matches!(*e, GetError::InvalidOutput(_)));
+                assert_eq!(1, missing.len());
+                assert_eq!("20231014_0100.png", missing[0].resource);
+            }
+            r => panic!("expected an incomplete fill, not {r:?}"),
+        }
+        assert!(store.join("20231014_0000.png").is_file());
+        assert!(!store.join("20231014_0100.png").exists());
+    }
+
+    #[test]
+    fn refresh() {
+        let (upstream, store, mut m) = local_mirror("refresh", "%Y%m%d_%H%M.txt", |cfg| {
+            cfg.refresh = Some(Refresh {
+                window: 60 * 60,
+                keep: false,
+            })
+        });
+        for h in 0..3 {
+            let f = upstream.join(format!("20231014_{h:02}00.txt"));
+            fs::write(f, format!("hour {h}")).unwrap();
+        }
+        assert!(m.refresh_range().is_some());
+        let hours = TimeRange::from((
+            Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 10, 14, 2, 0, 0).unwrap(),
+        ));
+        m.fill_captures(m.captures_in_range(&hours)).unwrap();
+        let r = m.refresh_captures(&hours);
+        assert_eq!(3, r.checked);
+        assert!(r.replaced.is_empty(), "nothing changed");
+
+        // touched, and so fetched again, but the same as it was
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(upstream.join("20231014_0200.txt"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let r = m.refresh_captures(&hours);
+        assert!(r.replaced.is_empty(), "same content");
+        assert!(r.failed.is_empty());
+        let meta = CaptureMeta::read(&store.join("20231014_0200.txt")).unwrap();
+        assert_eq!(Some(later.into()), meta.unwrap().remote_modified);
+
+        fs::write(upstream.join("20231014_0100.txt"), "hour 1, revised").unwrap();
+        let r = m.refresh_captures(&hours);
+        assert_eq!(
+            "3 checked, 1 changed upstream and replaced, 0 failed",
+            format!("{r}")
+        );
+        let path = store.join("20231014_0100.txt");
+        assert_eq!(path, r.replaced[0].path);
+        assert_eq!(15, r.replaced[0].meta.as_ref().unwrap().size);
+        assert_eq!("hour 1, revised", fs::read_to_string(&path).unwrap());
+        assert!(!store.join(store::REPLACED).exists(), "discarded");
+        assert!(m.refresh_captures(&hours).replaced.is_empty());
+
+        m.refresh.as_mut().unwrap().keep = true;
+        fs::write(upstream.join("20231014_0100.txt"), "hour 1, again").unwrap();
+        let r = m.refresh_captures(&hours);
+        assert_eq!(1, r.replaced.len());
+        assert_eq!("hour 1, again", fs::read_to_string(&path).unwrap());
+        let kept: Vec<_> = fs::read_dir(store.join(store::REPLACED))
+            .unwrap()
+            .map(|e| e.unwrap().path().join("20231014_0100.txt"))
+            .collect();
+        assert_eq!(1, kept.len());
+        assert_eq!("hour 1, revised", fs::read_to_string(&kept[0]).unwrap());
+        assert!(CaptureMeta::sidecar(&kept[0]).is_file(), "with its record");
+        let leftover = fs::read_dir(&store).unwrap().filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".new")
+        });
+        assert_eq!(0, leftover.count());
+    }
+
+    #[test]
+    fn listing_discovery() {
+        // an upstream which has drifted seven minutes from the hour,
+        // apart from midnight
+        let (upstream, store, mut m) = local_mirror("listing", "%Y/%m/%d/%Y%m%d_%H%M.txt", |cfg| {
+            cfg.discovery = Discovery::Listing
+        });
+        fs::create_dir_all(upstream.join("2023/10/14")).unwrap();
+        fs::write(upstream.join("2023/10/14/20231014_0000.txt"), "midnight").unwrap();
+        for h in 1..6 {
+            let f = upstream.join(format!("2023/10/14/20231014_{h:02}07.txt"));
+            fs::write(f, format!("hour {h}")).unwrap();
+        }
+        fs::write(upstream.join("2023/10/14/README.txt"), "not dated").unwrap();
+        assert_eq!(Discovery::Listing, m.discovery);
+
+        let day = TimeRange::from((
+            Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2023, 10, 14, 23, 59, 0).unwrap(),
+        ));
+        assert_eq!(vec!["2023/10/14/"], m.listing_dirs(&day));
+        let r = m.discover(&day).unwrap();
+        assert_eq!(6, r.captures.missing.len());
+        assert_eq!(23, r.absent.len());
+        assert_eq!("2023/10/14/20231014_0100.txt", r.absent[0].resource);
+        assert_eq!(5, r.unpredicted.len());
+        assert_eq!("2023/10/14/20231014_0107.txt", r.unpredicted[0].resource);
+        assert_eq!(
+            "6 listed, 23 predicted but absent, 5 present but unpredicted",
+            format!("{r}")
+        );
+
+        let filled = m.fill_captures(r.captures).unwrap();
+        assert_eq!(6, filled.len());
+        assert_eq!(
+            "hour 3",
+            fs::read_to_string(store.join("20231014_0307.txt")).unwrap()
+        );
+        let r = m.discover(&day).unwrap();
+        assert_eq!(6, r.captures.len());
+        assert!(r.captures.missing.is_empty());
+    }
+
+    #[test]
+    fn offline() {
+        // nothing listens on port 1
+        for remote in [
+            "ftp://127.0.0.1:1/pub/",
+            "sftp://reflector@127.0.0.1:1/pub/",
+        ] {
+            let mut cfg = mock_src_config();
+            cfg.remote = remote.to_string();
+            let mut m = Mirror::new(cfg).unwrap();
+            let day = TimeRange::from((
+                Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2023, 10, 14, 23, 59, 0).unwrap(),
+            ));
+            let cc = m.captures_in_range(&day);
+            assert_eq!(24, cc.missing.len());
+            let local = m.store_status().unwrap();
+            let s = m.status().unwrap();
+            assert_eq!(MirrorStatus::Unreachable(Box::new(local)), s);
+            assert!(format!("{s}").starts_with("remote unreachable; "));
+            assert!(matches!(m.ping(), Err(PingError::Unreachable(_))));
+            match m.fill_captures(cc) {
+                Err(GetError::IncompleteFill(e, missing)) => {
+                    assert!(matches!(*e, GetError::Unreachable(_)));
+                    assert_eq!(24, missing.len());
+                }
+                r => panic!("expected an incomplete fill, not {r:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn storage_root() {
+        let mut cfg = mock_src_config();
+        cfg.local = Some("relative".to_string());
+        assert!(matches!(Mirror::new(cfg.clone()), Err(NoStorageRoot(_))));
+        cfg.storage_root = Some(env::temp_dir());
+        cfg.local = Some("mock_mirror_store".to_string());
+        Mirror::new(cfg).unwrap();
+    }
+
+    #[test]
+    fn timezone() {
+        let mut cfg = mock_src_config();
+        assert_eq!(Tz::UTC, Mirror::new(cfg.clone()).unwrap().timezone);
+        cfg.timezone = Some(chrono_tz::America::New_York);
+        let m = Mirror::new(cfg).unwrap();
+        assert_eq!(chrono_tz::America::New_York, m.timezone);
+    }
+
+    #[test]
+    fn display() {
+        let m = mock_mirror();
+        assert_eq!(
+            "<mirror mock mirror source p: 1h o: 00:00 lp: 1d>",
+            format!("{m}")
+        );
+    }
+
     #[test]
     fn latest_capture() {
         let m = mock_mirror();
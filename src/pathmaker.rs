@@ -1,13 +1,21 @@
 pub mod error;
 pub use error::PathMakerError::{self, *};
+pub mod config;
+pub use config::PathMakerConfig;
 pub mod new;
 pub use new::new;
+pub mod registry;
+pub use registry::{register, PathMakerConstructor};
 pub mod sdo;
 pub use sdo::Sdo;
 pub mod goes_r;
 pub use goes_r::GoesR;
 pub mod identity;
 pub use identity::Identity;
+pub mod template;
+pub use template::Template;
+pub mod pattern;
+pub use pattern::Pattern;
 pub mod time;
 pub use time::make_utc;
 
@@ -24,10 +32,12 @@ pub trait PathMaker {
     fn filename_to_systime(&self, filename: &OsStr) -> Result<SystemTime, PathMakerError> {
         match self.filename_to_time(filename) {
             Ok(time) => {
-                let since: u64 = time.timestamp().try_into().unwrap();
+                let since: u64 = match time.timestamp().try_into() {
+                    Ok(s) => s,
+                    Err(_) => return Err(ImpossibleTimestamp(0)), // before the epoch
+                };
                 let dur = Duration::new(since, 0);
-                let st = SystemTime::now();
-                match st.checked_sub(dur) {
+                match SystemTime::UNIX_EPOCH.checked_add(dur) {
                     Some(st) => Ok(st),
                     None => Err(ImpossibleTimestamp(since)),
                 }
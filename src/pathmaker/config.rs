@@ -0,0 +1,169 @@
+// how to construct a PathMaker, as read from the configuration: either
+// the legacy space-separated string, e.g. "SDO _1024_0094.ogv", or a
+// table such as { kind = "goes-r", prefix = "MERGED_TrueColor_" }
+
+use super::{InvalidParameter, MissingParameter, PathMakerError, UnknownParameter};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use toml::Value;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(from = "PathMakerRepr")]
+pub struct PathMakerConfig {
+    pub kind: String,
+    pub params: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PathMakerRepr {
+    Legacy(String),
+    Table {
+        kind: String,
+        #[serde(flatten)]
+        params: BTreeMap<String, Value>,
+    },
+}
+
+impl From<PathMakerRepr> for PathMakerConfig {
+    fn from(r: PathMakerRepr) -> Self {
+        match r {
+            PathMakerRepr::Legacy(s) => Self::from(s.as_str()),
+            PathMakerRepr::Table { kind, params } => Self { kind, params },
+        }
+    }
+}
+
+impl PathMakerConfig {
+    pub fn new(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// add a string parameter, builder style
+    pub fn with(mut self, name: &str, value: &str) -> Self {
+        self.params
+            .insert(name.to_string(), Value::String(value.to_string()));
+        self
+    }
+
+    /// fetch an optional string parameter
+    pub fn str_param(&self, name: &str) -> Result<Option<&str>, PathMakerError> {
+        match self.params.get(name) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(v) => Err(InvalidParameter(
+                self.kind.clone(),
+                name.to_string(),
+                format!("expected a string, not {}", v.type_str()),
+            )),
+        }
+    }
+
+    /// fetch a required string parameter
+    pub fn required_str_param(&self, name: &str) -> Result<&str, PathMakerError> {
+        match self.str_param(name)? {
+            Some(s) => Ok(s),
+            None => Err(MissingParameter(self.kind.clone(), name.to_string())),
+        }
+    }
+
+    /// fail on any parameter not in the list of those known
+    pub fn only_params(&self, known: &[&str]) -> Result<(), PathMakerError> {
+        match self.params.keys().find(|k| !known.contains(&k.as_str())) {
+            Some(k) => Err(UnknownParameter(self.kind.clone(), k.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// an error for the named parameter
+    pub fn invalid(&self, name: &str, why: &str) -> PathMakerError {
+        InvalidParameter(self.kind.clone(), name.to_string(), why.to_string())
+    }
+}
+
+// the legacy string form: a kind, optionally followed by a space and a
+// single argument, which is the SDO suffix
+impl From<&str> for PathMakerConfig {
+    fn from(s: &str) -> Self {
+        let mut args = s.splitn(2, ' ');
+        let kind = args.next().unwrap_or("");
+        let cfg = Self::new(kind);
+        match args.next() {
+            Some(a) if kind.eq_ignore_ascii_case("sdo") => cfg.with("suffix", a),
+            Some(a) => cfg.with("arg", a), // rejected as an unknown parameter
+            None => cfg,
+        }
+    }
+}
+
+impl FromStr for PathMakerConfig {
+    type Err = PathMakerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl fmt::Display for PathMakerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (k, v) in self.params.iter() {
+            write!(f, " {k}={v}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Wrapper {
+        pathmaker: PathMakerConfig,
+    }
+
+    fn parse(s: &str) -> PathMakerConfig {
+        toml::from_str::<Wrapper>(s).unwrap().pathmaker
+    }
+
+    #[test]
+    fn legacy() {
+        let p = parse(r#"pathmaker = "SDO _1024_0094.ogv""#);
+        assert_eq!(
+            PathMakerConfig::new("SDO").with("suffix", "_1024_0094.ogv"),
+            p
+        );
+        let p = parse(r#"pathmaker = "GOES-R""#);
+        assert_eq!(PathMakerConfig::new("GOES-R"), p);
+    }
+
+    #[test]
+    fn table() {
+        let p = parse(r#"pathmaker = { kind = "goes-r", prefix = "MERGED_TrueColor_" }"#);
+        assert_eq!(
+            PathMakerConfig::new("goes-r").with("prefix", "MERGED_TrueColor_"),
+            p
+        );
+        assert_eq!(r#"goes-r prefix="MERGED_TrueColor_""#, format!("{p}"));
+    }
+
+    #[test]
+    fn params() {
+        let p = parse(r#"pathmaker = { kind = "sdo", suffix = 94, extra = "x" }"#);
+        assert!(matches!(p.str_param("suffix"), Err(InvalidParameter(..))));
+        assert!(matches!(
+            p.required_str_param("nope"),
+            Err(MissingParameter(..))
+        ));
+        assert_eq!(
+            Err(UnknownParameter("sdo".to_string(), "extra".to_string())),
+            p.only_params(&["suffix"])
+        );
+    }
+}
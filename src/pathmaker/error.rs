@@ -16,8 +16,13 @@ pub enum PathMakerError {
     UnparsableDay(String),
     UnparsableHour(String),
     UnparsableMinute(String),
+    UnparsableSecond(String),
     ImpossibleTimestamp(u64),
     MissingSeparator(char, String),
     MysteryError(String),
+    PatternMismatch(String),
+    MissingParameter(String, String),         // kind, parameter
+    UnknownParameter(String, String),         // kind, parameter
+    InvalidParameter(String, String, String), // kind, parameter, why
 }
 pub use PathMakerError::*;
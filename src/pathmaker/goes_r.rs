@@ -15,17 +15,34 @@ pub struct GoesR {
 
 const SUFFIX: &str = "z.png";
 const TIME_FMT: &str = "%Y%m%d_%H%M";
+const DEFAULT_PREFIX: &str = "ABI_TrueColor_";
 
 impl GoesR {
-    fn new(prefix: &str) -> GoesR {
+    pub fn new(prefix: &str) -> GoesR {
         let prefix = String::from(prefix);
         GoesR { prefix }
     }
 }
 
+impl TryFrom<&PathMakerConfig> for GoesR {
+    type Error = PathMakerError;
+
+    fn try_from(cfg: &PathMakerConfig) -> Result<Self, Self::Error> {
+        cfg.only_params(&["prefix"])?;
+        let prefix = cfg.str_param("prefix")?.unwrap_or(DEFAULT_PREFIX);
+        if prefix.is_empty() {
+            return Err(cfg.invalid("prefix", "must not be empty"));
+        }
+        if prefix.contains('/') {
+            return Err(cfg.invalid("prefix", "must not contain '/'"));
+        }
+        Ok(GoesR::new(prefix))
+    }
+}
+
 impl Default for GoesR {
     fn default() -> GoesR {
-        GoesR::new("ABI_TrueColor_")
+        GoesR::new(DEFAULT_PREFIX)
     }
 }
 
@@ -100,6 +117,19 @@ mod tests {
         assert_eq!(expect, p.filename_to_time(&f).unwrap());
     }
 
+    #[test]
+    fn from_config() {
+        let c = PathMakerConfig::new("goes-r");
+        assert_eq!(DEFAULT_PREFIX, GoesR::try_from(&c).unwrap().prefix);
+        let c = c.with("prefix", "MERGED_TrueColor_");
+        let p = GoesR::try_from(&c).unwrap();
+        let t = Utc.with_ymd_and_hms(2023, 10, 14, 15, 10, 0).unwrap();
+        let expect = "MERGED_TrueColor_20231014_1510z.png";
+        assert_eq!(OsString::from(expect), p.time_to_filename(&t));
+        let c = PathMakerConfig::new("goes-r").with("prefix", "");
+        assert!(matches!(GoesR::try_from(&c), Err(InvalidParameter(..))));
+    }
+
     #[test]
     fn goes16_dogfood() {
         let p = GoesR::new("testing_");
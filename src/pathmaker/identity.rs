@@ -11,6 +11,15 @@ impl Identity {
     }
 }
 
+impl TryFrom<&PathMakerConfig> for Identity {
+    type Error = PathMakerError;
+
+    fn try_from(cfg: &PathMakerConfig) -> Result<Self, Self::Error> {
+        cfg.only_params(&[])?;
+        Ok(Identity::new())
+    }
+}
+
 impl Default for Identity {
     fn default() -> Self {
         Identity::new()
@@ -1,15 +1,53 @@
-use super::{GoesR, Identity, Sdo}; // trait objects
-use super::{NoNameErr, PathMaker, PathMakerError, UnknownName};
-
-pub fn new(s: &str) -> Result<Box<dyn PathMaker>, PathMakerError> {
-    // sort of an ugly pseudo space split argument system
-    // we should take a &str iterator as argument...
-    let mut args = s.split(' ');
-    match args.next() {
-        Some("SDO") => Ok(Box::<Sdo>::new(Sdo::new(args.next().unwrap()))),
-        Some("GOES-R") => Ok(Box::<GoesR>::default()),
-        Some("identity") => Ok(Box::<Identity>::default()),
-        Some("") => Err(NoNameErr),
-        _ => Err(UnknownName(s.to_string())),
+use super::registry::lookup;
+use super::{NoNameErr, PathMaker, PathMakerConfig, PathMakerError, UnknownName};
+
+/// construct a PathMaker of the configured kind, using the registry
+pub fn new(cfg: &PathMakerConfig) -> Result<Box<dyn PathMaker>, PathMakerError> {
+    if cfg.kind.is_empty() {
+        return Err(NoNameErr);
+    }
+    match lookup(&cfg.kind) {
+        Some(constructor) => constructor(cfg),
+        None => Err(UnknownName(cfg.kind.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathmaker::{InvalidParameter, MissingParameter, UnknownParameter};
+
+    #[test]
+    fn known() {
+        new(&PathMakerConfig::from("SDO _1024_0094.ogv")).unwrap();
+        new(&PathMakerConfig::from("GOES-R")).unwrap();
+        new(&PathMakerConfig::new("goes-r").with("prefix", "MERGED_TrueColor_")).unwrap();
+        new(&PathMakerConfig::from("identity")).unwrap();
+        new(&PathMakerConfig::new("template").with("format", "%Y%m%d.png")).unwrap();
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(matches!(new(&PathMakerConfig::from("")), Err(NoNameErr)));
+        assert!(matches!(
+            new(&PathMakerConfig::from("GOES")),
+            Err(UnknownName(_))
+        ));
+    }
+
+    #[test]
+    fn bad_parameters() {
+        assert!(matches!(
+            new(&PathMakerConfig::from("SDO")),
+            Err(MissingParameter(..))
+        ));
+        assert!(matches!(
+            new(&PathMakerConfig::from("GOES-R extra")),
+            Err(UnknownParameter(..))
+        ));
+        assert!(matches!(
+            new(&PathMakerConfig::new("goes-r").with("prefix", "a/b")),
+            Err(InvalidParameter(..))
+        ));
     }
 }
@@ -0,0 +1,228 @@
+// a PathMaker which dates filenames using a regex with named groups,
+// for upstream files with components which can't be predicted from
+// the time alone, e.g. the SDO variants
+//   20231013_1024_0094.ogv
+//   20231013_588_SDO_VO2.mp4
+// recognized groups are year, month, day, doy (day of year), hour,
+// minute and second; year is required, as is either month and day, or
+// doy, while the time of day defaults to midnight
+//
+// since a regex can't be used to make a filename, a strftime format
+// is needed for that, and the pattern must date what it makes
+
+use super::*;
+use chrono::{DateTime, NaiveDate, TimeZone, Timelike, Utc};
+use regex::{Captures, Regex};
+use std::ffi::{OsStr, OsString};
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub pattern: Regex,
+    pub format: String,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, format: &str) -> Result<Pattern, PathMakerError> {
+        let cfg = PathMakerConfig::new("pattern")
+            .with("pattern", pattern)
+            .with("format", format);
+        Self::try_from(&cfg)
+    }
+
+    // match the whole filename, else just the last path component
+    fn captures<'a>(&self, filename: &'a str) -> Option<Captures<'a>> {
+        match self.pattern.captures(filename) {
+            Some(c) => Some(c),
+            None => match filename.rsplit_once('/') {
+                Some((_, base)) => self.pattern.captures(base),
+                None => None,
+            },
+        }
+    }
+}
+
+impl TryFrom<&PathMakerConfig> for Pattern {
+    type Error = PathMakerError;
+
+    fn try_from(cfg: &PathMakerConfig) -> Result<Self, Self::Error> {
+        cfg.only_params(&["pattern", "format"])?;
+        let pattern = match Regex::new(cfg.required_str_param("pattern")?) {
+            Ok(r) => r,
+            Err(e) => return Err(cfg.invalid("pattern", &e.to_string())),
+        };
+        let groups: Vec<String> = pattern
+            .capture_names()
+            .flatten()
+            .map(String::from)
+            .collect();
+        let has = |g: &str| groups.iter().any(|n| n == g);
+        if !has("year") {
+            return Err(cfg.invalid("pattern", "requires a 'year' group"));
+        }
+        let dated = has("doy") || (has("month") && has("day"));
+        if !dated {
+            return Err(cfg.invalid(
+                "pattern",
+                "requires 'month' and 'day' groups, or a 'doy' group",
+            ));
+        }
+        let format = cfg.required_str_param("format")?;
+        if format.is_empty() {
+            return Err(cfg.invalid("format", "must not be empty"));
+        }
+        let p = Pattern {
+            pattern,
+            format: format.to_string(),
+        };
+
+        // make sure we date what we generate as the time it was made from
+        let sample = Utc.with_ymd_and_hms(2001, 2, 3, 4, 5, 6).unwrap();
+        let agrees = match p.filename_to_time(&p.time_to_filename(&sample)) {
+            Ok(t) => {
+                t.date_naive() == sample.date_naive()
+                    && (!has("hour") || t.hour() == sample.hour())
+                    && (!has("minute") || t.minute() == sample.minute())
+                    && (!has("second") || t.second() == sample.second())
+            }
+            Err(_) => false,
+        };
+        if !agrees {
+            return Err(cfg.invalid("format", "makes filenames the pattern doesn't date"));
+        }
+        Ok(p)
+    }
+}
+
+fn group<T: std::str::FromStr>(
+    c: &Captures,
+    name: &str,
+    err: fn(String) -> PathMakerError,
+) -> Result<Option<T>, PathMakerError> {
+    match c.name(name) {
+        Some(m) => match m.as_str().parse() {
+            Ok(x) => Ok(Some(x)),
+            Err(_) => Err(err(m.as_str().to_string())),
+        },
+        None => Ok(None),
+    }
+}
+
+impl PathMaker for Pattern {
+    fn time_to_filename(&self, time: &DateTime<Utc>) -> OsString {
+        OsString::from(time.format(&self.format).to_string())
+    }
+
+    fn filename_to_time(&self, filename: &OsStr) -> Result<DateTime<Utc>, PathMakerError> {
+        let filename = match filename.to_str() {
+            Some(f) => f,
+            None => return Err(NoFileNameErr),
+        };
+        let c = match self.captures(filename) {
+            Some(c) => c,
+            None => return Err(PatternMismatch(filename.to_string())),
+        };
+
+        let year: i32 = match group(&c, "year", UnparsableYear)? {
+            Some(y) => y,
+            None => return Err(UnparsableYear("".to_string())),
+        };
+        let hour = group(&c, "hour", UnparsableHour)?.unwrap_or(0);
+        let min = group(&c, "minute", UnparsableMinute)?.unwrap_or(0);
+        let sec = group(&c, "second", UnparsableSecond)?.unwrap_or(0);
+
+        if let Some(doy) = group::<u32>(&c, "doy", UnparsableDay)? {
+            let date = match NaiveDate::from_yo_opt(year, doy) {
+                Some(d) => d,
+                None => return Err(UnparsableDay(doy.to_string())),
+            };
+            return match date.and_hms_opt(hour, min, sec) {
+                Some(d) => Ok(Utc.from_utc_datetime(&d)),
+                None => Err(NoTimeError(year, 0, doy, hour, min, sec)),
+            };
+        }
+        let mon = group(&c, "month", UnparsableMonth)?.unwrap_or(0);
+        let day = group(&c, "day", UnparsableDay)?.unwrap_or(0);
+        make_utc(year, mon, day, hour, min, sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDO_PATTERN: &str = r"^(?<year>\d{4})(?<month>\d\d)(?<day>\d\d)_[^/]+\.(ogv|mp4)$";
+    const SDO_FORMAT: &str = "%Y/%m/%d/%Y%m%d_1024_0094.ogv";
+
+    #[test]
+    fn variants() {
+        let p = Pattern::new(SDO_PATTERN, SDO_FORMAT).unwrap();
+        let expect = Utc.with_ymd_and_hms(2023, 10, 13, 0, 0, 0).unwrap();
+        for f in [
+            "20231013_1024_0094.ogv",
+            "20231013_588_SDO_VO2.mp4",
+            "2023/10/13/20231013_588_SDO_VO2.mp4",
+        ] {
+            assert_eq!(expect, p.filename_to_time(&OsString::from(f)).unwrap());
+        }
+        assert_eq!(
+            Err(PatternMismatch("README.txt".to_string())),
+            p.filename_to_time(&OsString::from("README.txt"))
+        );
+        assert_eq!(
+            OsString::from("2023/10/13/20231013_1024_0094.ogv"),
+            p.time_to_filename(&expect)
+        );
+    }
+
+    #[test]
+    fn day_of_year() {
+        let p = Pattern::new(
+            r"^img_(?<year>\d{4})(?<doy>\d{3})_(?<hour>\d\d)(?<minute>\d\d)\.jpg$",
+            "img_%Y%j_%H%M.jpg",
+        )
+        .unwrap();
+        let t = Utc.with_ymd_and_hms(2024, 2, 29, 23, 50, 0).unwrap();
+        let f = p.time_to_filename(&t);
+        assert_eq!(OsString::from("img_2024060_2350.jpg"), f);
+        assert_eq!(t, p.filename_to_time(&f).unwrap());
+        assert!(p
+            .filename_to_time(&OsString::from("img_2023366_0000.jpg"))
+            .is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            Pattern::new("(", SDO_FORMAT),
+            Err(InvalidParameter(..))
+        ));
+        assert!(matches!(
+            Pattern::new(r"(?<month>\d\d)(?<day>\d\d)", SDO_FORMAT),
+            Err(InvalidParameter(..))
+        ));
+        assert!(matches!(
+            Pattern::new(r"(?<year>\d{4})(?<month>\d\d)", SDO_FORMAT),
+            Err(InvalidParameter(..))
+        ));
+        let cfg = PathMakerConfig::new("pattern").with("pattern", SDO_PATTERN);
+        assert!(matches!(Pattern::try_from(&cfg), Err(MissingParameter(..))));
+
+        // formats the pattern can't read back
+        for format in [
+            "%Y%m%d_1024_0094.png",
+            "%Y%d%m_1024_0094.ogv",
+            "%Y/%m/%d/1024_0094.ogv",
+        ] {
+            assert!(
+                matches!(Pattern::new(SDO_PATTERN, format), Err(InvalidParameter(..))),
+                "{format}"
+            );
+        }
+        let p = r"^(?<year>\d{4})(?<month>\d\d)(?<day>\d\d)_(?<hour>\d\d)\.png$";
+        assert!(Pattern::new(p, "%Y%m%d_%H.png").is_ok());
+        assert!(matches!(
+            Pattern::new(p, "%Y%m%d_%M.png"),
+            Err(InvalidParameter(..))
+        ));
+    }
+}
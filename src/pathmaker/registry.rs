@@ -0,0 +1,102 @@
+// a runtime registry of PathMaker constructors, keyed by the kind used
+// in PathMakerConfig; the built-in kinds are always present, and
+// library users may register their own before building mirrors
+
+use super::{GoesR, Identity, Pattern, Sdo, Template}; // trait objects
+use super::{PathMaker, PathMakerConfig, PathMakerError};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub type PathMakerConstructor =
+    Arc<dyn Fn(&PathMakerConfig) -> Result<Box<dyn PathMaker>, PathMakerError> + Send + Sync>;
+
+static REGISTRY: OnceLock<RwLock<HashMap<String, PathMakerConstructor>>> = OnceLock::new();
+
+fn builtins() -> HashMap<String, PathMakerConstructor> {
+    let mut m: HashMap<String, PathMakerConstructor> = HashMap::new();
+    m.insert(
+        "sdo".to_string(),
+        Arc::new(|c| Ok(Box::new(Sdo::try_from(c)?))),
+    );
+    m.insert(
+        "goes-r".to_string(),
+        Arc::new(|c| Ok(Box::new(GoesR::try_from(c)?))),
+    );
+    m.insert(
+        "identity".to_string(),
+        Arc::new(|c| Ok(Box::new(Identity::try_from(c)?))),
+    );
+    m.insert(
+        "template".to_string(),
+        Arc::new(|c| Ok(Box::new(Template::try_from(c)?))),
+    );
+    m.insert(
+        "pattern".to_string(),
+        Arc::new(|c| Ok(Box::new(Pattern::try_from(c)?))),
+    );
+    m
+}
+
+fn registry() -> &'static RwLock<HashMap<String, PathMakerConstructor>> {
+    REGISTRY.get_or_init(|| RwLock::new(builtins()))
+}
+
+/// register a constructor for a kind of PathMaker, replacing any
+/// constructor already registered for that kind; kinds are matched
+/// without regard to case
+/// ```
+/// use reflector::pathmaker::{self, Identity, PathMakerConfig};
+///
+/// pathmaker::register("in-house", |cfg| {
+///     cfg.only_params(&[])?;
+///     Ok(Box::new(Identity::new()))
+/// });
+/// assert!(pathmaker::new(&PathMakerConfig::new("In-House")).is_ok());
+/// ```
+pub fn register<F>(kind: &str, constructor: F)
+where
+    F: Fn(&PathMakerConfig) -> Result<Box<dyn PathMaker>, PathMakerError> + Send + Sync + 'static,
+{
+    let mut r = registry().write().unwrap();
+    r.insert(kind.to_ascii_lowercase(), Arc::new(constructor));
+}
+
+/// the constructor registered for a kind, if any
+pub fn lookup(kind: &str) -> Option<PathMakerConstructor> {
+    let r = registry().read().unwrap();
+    r.get(&kind.to_ascii_lowercase()).cloned()
+}
+
+/// all registered kinds, sorted
+pub fn kinds() -> Vec<String> {
+    let r = registry().read().unwrap();
+    let mut k: Vec<String> = r.keys().cloned().collect();
+    k.sort();
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathmaker::{new, Identity, UnknownName};
+
+    #[test]
+    fn builtin_kinds() {
+        let k = kinds();
+        for b in ["goes-r", "identity", "pattern", "sdo", "template"] {
+            assert!(k.contains(&b.to_string()), "{b} is registered");
+        }
+    }
+
+    #[test]
+    fn custom() {
+        let cfg = PathMakerConfig::new("registry-test");
+        assert!(matches!(new(&cfg), Err(UnknownName(_))));
+        register("Registry-Test", |c| {
+            c.only_params(&[])?;
+            Ok(Box::new(Identity::new()))
+        });
+        assert!(lookup("registry-test").is_some());
+        assert!(new(&cfg).is_ok());
+    }
+}
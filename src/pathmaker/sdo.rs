@@ -21,6 +21,22 @@ impl Sdo {
     }
 }
 
+impl TryFrom<&PathMakerConfig> for Sdo {
+    type Error = PathMakerError;
+
+    fn try_from(cfg: &PathMakerConfig) -> Result<Self, Self::Error> {
+        cfg.only_params(&["suffix"])?;
+        let suffix = cfg.required_str_param("suffix")?;
+        if suffix.contains('/') {
+            return Err(cfg.invalid("suffix", "must not contain '/'"));
+        }
+        if suffix.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(cfg.invalid("suffix", "must not start with a digit"));
+        }
+        Ok(Sdo::new(suffix))
+    }
+}
+
 // default here is pretty useless but helpful to have for testing/mocking
 impl Default for Sdo {
     fn default() -> Sdo {
@@ -89,6 +105,14 @@ mod tests {
         assert_eq!(expect, p.filename_to_time(&f).unwrap());
     }
 
+    #[test]
+    fn from_config() {
+        let c = PathMakerConfig::new("sdo").with("suffix", "_1024_0094.ogv");
+        assert_eq!("_1024_0094.ogv", Sdo::try_from(&c).unwrap().suffix);
+        let c = PathMakerConfig::new("sdo").with("suffix", "1024_0094.ogv");
+        assert!(matches!(Sdo::try_from(&c), Err(InvalidParameter(..))));
+    }
+
     #[test]
     fn sdo_dogfood() {
         let p = Sdo::new("_some_random.ogv");
@@ -0,0 +1,136 @@
+// a PathMaker built from a single strftime template, which is used
+// both to format and to parse filenames, e.g.
+//   "%Y/%m/%d/%Y%m%d_1024_0094.ogv"
+//   "ABI_TrueColor_%Y%m%d_%H%Mz.png"
+// fields missing from the template, typically the time of day, are
+// taken as zero when parsing
+
+use super::*;
+use chrono::format::{parse, Item, Parsed, StrftimeItems};
+use chrono::{DateTime, TimeZone, Utc};
+use std::ffi::{OsStr, OsString};
+
+#[derive(Clone, Debug)]
+pub struct Template {
+    pub format: String,
+}
+
+impl Template {
+    pub fn new(format: &str) -> Result<Template, PathMakerError> {
+        Self::try_from(&PathMakerConfig::new("template").with("format", format))
+    }
+}
+
+impl TryFrom<&PathMakerConfig> for Template {
+    type Error = PathMakerError;
+
+    fn try_from(cfg: &PathMakerConfig) -> Result<Self, Self::Error> {
+        cfg.only_params(&["format"])?;
+        let format = cfg.required_str_param("format")?;
+        if format.is_empty() {
+            return Err(cfg.invalid("format", "must not be empty"));
+        }
+        if StrftimeItems::new(format).any(|i| matches!(i, Item::Error)) {
+            return Err(cfg.invalid("format", "is not a valid strftime format"));
+        }
+        let t = Template {
+            format: format.to_string(),
+        };
+
+        // make sure a time can be recovered from what we generate
+        let sample = Utc.with_ymd_and_hms(2001, 2, 3, 4, 5, 6).unwrap();
+        if t.filename_to_time(&t.time_to_filename(&sample)).is_err() {
+            return Err(cfg.invalid("format", "does not include a complete date"));
+        }
+        Ok(t)
+    }
+}
+
+impl PathMaker for Template {
+    fn time_to_filename(&self, time: &DateTime<Utc>) -> OsString {
+        OsString::from(time.format(&self.format).to_string())
+    }
+
+    fn filename_to_time(&self, filename: &OsStr) -> Result<DateTime<Utc>, PathMakerError> {
+        let filename = match filename.to_str() {
+            Some(f) => f,
+            None => return Err(NoFileNameErr),
+        };
+
+        // use only as many trailing path components of the template
+        // as the filename has, so flattened filenames parse as well
+        let fparts: Vec<&str> = filename.split('/').collect();
+        let tparts: Vec<&str> = self.format.split('/').collect();
+        let keep = fparts.len().min(tparts.len());
+        let filename = fparts[fparts.len() - keep..].join("/");
+        let format = tparts[tparts.len() - keep..].join("/");
+
+        let mut p = Parsed::new();
+        if let Err(e) = parse(&mut p, &filename, StrftimeItems::new(&format)) {
+            return Err(TimeParseError(filename, e));
+        }
+        // default the time of day to midnight
+        if p.hour_div_12().is_none() && p.hour_mod_12().is_none() {
+            let _ = p.set_hour(0);
+        }
+        if p.minute().is_none() {
+            let _ = p.set_minute(0);
+        }
+        if p.second().is_none() {
+            let _ = p.set_second(0);
+        }
+        match p.to_naive_datetime_with_offset(0) {
+            Ok(d) => Ok(Utc.from_utc_datetime(&d)),
+            Err(e) => Err(TimeParseError(filename, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdo_like() {
+        let p = Template::new("%Y/%m/%d/%Y%m%d_1024_0094.ogv").unwrap();
+        let t = Utc.with_ymd_and_hms(2023, 9, 23, 0, 0, 0).unwrap();
+        let f = OsString::from("2023/09/23/20230923_1024_0094.ogv");
+        assert_eq!(f, p.time_to_filename(&t));
+        assert_eq!(t, p.filename_to_time(&f).unwrap());
+
+        // as flattened into the store
+        let f = OsString::from("20230923_1024_0094.ogv");
+        assert_eq!(t, p.filename_to_time(&f).unwrap());
+    }
+
+    #[test]
+    fn goes_like() {
+        let p = Template::new("ABI_TrueColor_%Y%m%d_%H%Mz.png").unwrap();
+        let t = Utc.with_ymd_and_hms(2023, 10, 14, 15, 0, 0).unwrap();
+        let f = OsString::from("ABI_TrueColor_20231014_1500z.png");
+        assert_eq!(f, p.time_to_filename(&t));
+        assert_eq!(t, p.filename_to_time(&f).unwrap());
+        assert!(p
+            .filename_to_time(&OsString::from("WST_TrueColor_20231014_1500z.png"))
+            .is_err());
+    }
+
+    #[test]
+    fn dogfood() {
+        let p = Template::new("%Y%j/%H%M%S.jpg").unwrap();
+        let t = Utc::now();
+        let f = p.time_to_filename(&t);
+        let tt = p.filename_to_time(&f).unwrap();
+        assert_eq!(f, p.time_to_filename(&tt));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Template::new(""), Err(InvalidParameter(..))));
+        assert!(matches!(Template::new("%Q.png"), Err(InvalidParameter(..))));
+        assert!(matches!(
+            Template::new("%H%M.png"),
+            Err(InvalidParameter(..))
+        ));
+    }
+}
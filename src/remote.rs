@@ -1,15 +1,30 @@
 //! Protocol engines, used to retrieve files from remote sites.
 
 pub mod client;
-pub use client::{ConnectError, GetError, ListError, PingError, RemoteClient};
+pub use client::redacted;
+pub(crate) use client::{select_entries, split_prefix};
+pub use client::{
+    ConnectError, Fetched, GetError, ListError, PingError, RemoteClient, RemoteEntry, RemoteStat,
+};
 pub mod factory;
-pub use factory::{from_url, RCFactoryError};
+pub use factory::{
+    from_url, from_url_with_options, register, RCFactoryError, RemoteClientConstructor,
+};
 pub mod gotten;
 pub use gotten::{Gotten, GottenValidation};
 pub mod http;
 pub use http::Http;
 pub mod ftp;
-pub use ftp::Ftp;
+pub use ftp::{Ftp, FtpCredentials, FtpOptions, FtpTls};
+pub mod local;
+pub mod netrc;
+pub use local::Local;
+pub mod options;
+pub use options::RemoteOptions;
+pub mod partial;
+pub use partial::Partial;
+pub mod sftp;
+pub use sftp::{Sftp, SftpAuth, SftpCredentials};
 
 // TODO: test only?
 pub mod mock;
@@ -1,23 +1,109 @@
 use super::*;
 // use std::convert::From;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
 use url::Url;
 
 #[derive(Debug)]
 pub enum RCFactoryError {
     Unimplemented,
     NoHandlerForScheme(String),
-    FtpError(ConnectError),
+    LocalError(ConnectError),
+    MissingOption(String),
+    UnknownOption(String),
+    InvalidOption(String, String),
+    ClientSetupError(String), // for use by registered clients
 }
 use RCFactoryError::*;
 
+pub type RemoteClientConstructor = Arc<
+    dyn Fn(&Url, &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError> + Send + Sync,
+>;
+
+// constructors for each URL scheme; the built-in schemes are always
+// present, and library users may register their own
+static REGISTRY: OnceLock<RwLock<HashMap<String, RemoteClientConstructor>>> = OnceLock::new();
+
+fn http(url: &Url, opts: &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
+    opts.only_params(&[])?;
+    Ok(Box::new(Http::new(url)))
+}
+
+fn ftp(url: &Url, opts: &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
+    let options = FtpOptions::try_from((url, opts))?;
+    let creds = FtpCredentials::try_from((url, opts))?;
+    Ok(Box::new(Ftp::new(url, Some(creds), options)))
+}
+
+fn local(url: &Url, opts: &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
+    opts.only_params(&[])?;
+    match Local::new(url) {
+        Ok(l) => Ok(Box::new(l)),
+        Err(e) => Err(LocalError(e)),
+    }
+}
+
+fn sftp(url: &Url, opts: &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
+    let creds = SftpCredentials::try_from((url, opts))?;
+    Ok(Box::new(Sftp::new(url, creds)))
+}
+
+fn registry() -> &'static RwLock<HashMap<String, RemoteClientConstructor>> {
+    REGISTRY.get_or_init(|| {
+        let mut m: HashMap<String, RemoteClientConstructor> = HashMap::new();
+        m.insert("http".to_string(), Arc::new(http));
+        m.insert("https".to_string(), Arc::new(http));
+        m.insert("ftp".to_string(), Arc::new(ftp));
+        m.insert("ftps".to_string(), Arc::new(ftp));
+        m.insert("file".to_string(), Arc::new(local));
+        m.insert("sftp".to_string(), Arc::new(sftp));
+        RwLock::new(m)
+    })
+}
+
+/// register a constructor for the RemoteClient handling a URL scheme,
+/// replacing any constructor already registered for that scheme
+/// ```
+/// use reflector::remote::{self, mock::Mock};
+/// use url::Url;
+///
+/// remote::register("mock", |_url, _opts| Ok(Box::new(Mock())));
+/// let u = Url::parse("mock://example/").unwrap();
+/// assert!(remote::from_url(&u).is_ok());
+/// ```
+pub fn register<F>(scheme: &str, constructor: F)
+where
+    F: Fn(&Url, &RemoteOptions) -> Result<Box<dyn RemoteClient>, RCFactoryError>
+        + Send
+        + Sync
+        + 'static,
+{
+    let mut r = registry().write().unwrap();
+    r.insert(scheme.to_ascii_lowercase(), Arc::new(constructor));
+}
+
+/// all registered schemes, sorted
+pub fn schemes() -> Vec<String> {
+    let r = registry().read().unwrap();
+    let mut s: Vec<String> = r.keys().cloned().collect();
+    s.sort();
+    s
+}
+
 pub fn from_url(url: &Url) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
-    match url.scheme() {
-        "http" | "https" => Ok(Box::new(Http::new(url))),
-        "ftp" => match Ftp::new(url, None) {
-            Ok(f) => Ok(Box::new(f)),
-            Err(e) => Err(FtpError(e)),
-        },
-        x => Err(NoHandlerForScheme(x.to_string())),
+    from_url_with_options(url, &RemoteOptions::default())
+}
+
+/// construct the client for the URL, passing it the source's options
+pub fn from_url_with_options(
+    url: &Url,
+    opts: &RemoteOptions,
+) -> Result<Box<dyn RemoteClient>, RCFactoryError> {
+    // url always lowercases the scheme
+    let constructor = registry().read().unwrap().get(url.scheme()).cloned();
+    match constructor {
+        Some(c) => c(url, opts),
+        None => Err(NoHandlerForScheme(url.scheme().to_string())),
     }
 }
 
@@ -51,6 +137,31 @@ mod tests {
         });
     }
 
+    #[test]
+    fn local() {
+        let u = Url::parse("file:///tmp/").unwrap();
+        let mut rc = from_url(&u).unwrap();
+        rc.ping().unwrap();
+        let u = Url::parse("file://elsewhere/tmp/").unwrap();
+        assert!(matches!(from_url(&u), Err(LocalError(_))));
+        let u = Url::parse("file:///tmp/").unwrap();
+        let opts = RemoteOptions::new().with("user", "me");
+        assert!(matches!(
+            from_url_with_options(&u, &opts),
+            Err(UnknownOption(_))
+        ));
+    }
+
+    #[test]
+    fn registered() {
+        let u = Url::parse("factory-test://somewhere/").unwrap();
+        assert!(matches!(from_url(&u), Err(NoHandlerForScheme(_))));
+        register("Factory-Test", |_, _| Ok(Box::new(mock::Mock())));
+        assert!(schemes().contains(&"factory-test".to_string()));
+        let mut rc = from_url(&u).unwrap();
+        rc.ping().unwrap();
+    }
+
     // FIXME: not sure how to write this test
     // #[test]
     // fn try_from() {
@@ -1,6 +1,9 @@
+use super::RemoteStat;
+use crate::media::MediaType;
 use std::cmp::Ordering;
 use std::io;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use url::Url;
 
 pub struct Gotten {
@@ -9,6 +12,9 @@ pub struct Gotten {
     pub source: Url,
     pub output: PathBuf,
     pub size: u64,
+    pub remote_size: Option<u64>, // as upstream gave it, if it did
+    pub remote_modified: Option<SystemTime>, // likewise
+    pub etag: Option<String>,     // likewise, over HTTP
 }
 
 #[derive(Debug)]
@@ -19,6 +25,10 @@ pub enum GottenValidation {
     Tempdir(io::Error),
     OutputLargerThanExpected(u64, u64),
     OutputSmallerThanExpected(u64, u64),
+    OutputNotRemoteSize(u64, u64),
+    OutputRead(io::Error),
+    UnexpectedContentType(String, MediaType),
+    NotOfMediaType(MediaType),
 }
 use GottenValidation::*;
 
@@ -32,9 +42,24 @@ impl Gotten {
             source,
             output,
             size,
+            remote_size: None,
+            remote_modified: None,
+            etag: None,
         }
     }
 
+    /// with what upstream said of the file
+    pub fn with_remote(mut self, stat: RemoteStat) -> Gotten {
+        self.remote_size = stat.size;
+        self.remote_modified = stat.modified;
+        self
+    }
+
+    /// the media type expected, by the resource's name
+    pub fn media_type(&self) -> Option<MediaType> {
+        MediaType::from_name(&self.resource)
+    }
+
     pub fn validate(&self) -> Result<(), GottenValidation> {
         if !self.output.is_file() {
             return Err(OutputDoesNotExist);
@@ -46,9 +71,28 @@ impl Gotten {
         let gs = m.len();
         let es = self.size;
         match gs.cmp(&es) {
-            Ordering::Greater => Err(OutputLargerThanExpected(gs, es)),
-            Ordering::Less => Err(OutputSmallerThanExpected(gs, es)),
-            _ => Ok(()),
+            Ordering::Greater => return Err(OutputLargerThanExpected(gs, es)),
+            Ordering::Less => return Err(OutputSmallerThanExpected(gs, es)),
+            _ => (),
+        }
+        // a short read counts what it got, but upstream knows better
+        if let Some(rs) = self.remote_size {
+            if gs != rs {
+                return Err(OutputNotRemoteSize(gs, rs));
+            }
+        }
+        let media = match self.media_type() {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        // such as an HTML error page, served as the image
+        if !media.allows(&self.mimetype) {
+            return Err(UnexpectedContentType(self.mimetype.clone(), media));
+        }
+        match MediaType::sniff_file(&self.output) {
+            Ok(Some(m)) if m == media => Ok(()),
+            Ok(_) => Err(NotOfMediaType(media)),
+            Err(e) => Err(OutputRead(e)),
         }
     }
 
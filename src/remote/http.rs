@@ -1,12 +1,15 @@
 // HTTP and HTTPS remote client
 
 use super::*;
-use log::debug;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::{debug, warn};
+use percent_encoding::percent_decode_str;
+use regex::Regex;
 use std::io::{BufWriter, Write};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use ureq;
 use url::{ParseError, Url};
 
@@ -42,10 +45,16 @@ impl FromStr for Http {
 }
 
 impl RemoteClient for Http {
-    fn remote_addr(&self) -> SocketAddr {
-        let host = self.base.host_str().unwrap();
-        let port = self.base.port_or_known_default().unwrap();
-        (host, port).to_socket_addrs().unwrap().next().unwrap()
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        let host = self.base.host_str()?;
+        let port = self.base.port_or_known_default()?;
+        match (host, port).to_socket_addrs() {
+            Ok(mut a) => a.next(),
+            Err(e) => {
+                debug!("cannot resolve {host}: {e}");
+                None
+            }
+        }
     }
 
     fn url(&self, resource: &str) -> Result<Url, GetError> {
@@ -62,7 +71,7 @@ impl RemoteClient for Http {
         }
     }
 
-    fn exists(&self, resource: &str) -> Result<bool, GetError> {
+    fn exists(&mut self, resource: &str) -> Result<bool, GetError> {
         let u = self.url(resource)?;
         match self.agent.request_url("HEAD", &u).call() {
             Ok(_) => Ok(true),
@@ -71,60 +80,233 @@ impl RemoteClient for Http {
         }
     }
 
+    fn stat(&mut self, resource: &str) -> Result<RemoteStat, GetError> {
+        let u = self.url(resource)?;
+        let resp = match self.agent.request_url("HEAD", &u).call() {
+            Ok(r) => r,
+            Err(ureq::Error::Status(404, _)) => {
+                return Err(GetError::NotFound(resource.to_string()))
+            }
+            Err(e) => return Err(GetError::RequestErr(Box::new(e))),
+        };
+        Ok(remote_stat(&resp))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<RemoteEntry>, ListError> {
+        let dir = match self.base.join(split_prefix(prefix).0) {
+            Ok(u) => u,
+            Err(e) => return Err(ListError::UnparsableURL(e)),
+        };
+        let resp = match self.agent.request_url("GET", &dir).call() {
+            Ok(r) => r,
+            Err(ureq::Error::Status(404, _)) => return Ok(Vec::new()),
+            Err(e) => return Err(ListError::RequestErr(Box::new(e))),
+        };
+        let html = match resp.into_string() {
+            Ok(s) => s,
+            Err(e) => return Err(ListError::ReadIndexError(e)),
+        };
+        Ok(select_entries(prefix, parse_autoindex(&dir, &html)))
+    }
+
     fn get(&mut self, resource: &str, output: PathBuf) -> Result<Gotten, GetError> {
+        match self.fetch(resource, output, None)? {
+            Some(g) => Ok(g),
+            None => unreachable!("an unconditional GET always fetches"),
+        }
+    }
+
+    // a conditional GET, which upstream answers with 304 Not Modified
+    // if the file is as it was
+    fn get_if_changed(
+        &mut self,
+        resource: &str,
+        output: PathBuf,
+        fetched: &Fetched,
+    ) -> Result<Option<Gotten>, GetError> {
+        self.fetch(resource, output, Some(fetched))
+    }
+}
+
+impl Http {
+    // get a file, or only if it's changed since it was last fetched
+    fn fetch(
+        &mut self,
+        resource: &str,
+        output: PathBuf,
+        since: Option<&Fetched>,
+    ) -> Result<Option<Gotten>, GetError> {
         let u = self.url(resource)?;
-        let resp = match self.agent.request_url("GET", &u).call() {
+        let mut partial = self.create_output(&output)?;
+        let mut req = self.agent.request_url("GET", &u);
+        let mut offset = 0;
+        if let Some(f) = since {
+            if let Some(etag) = &f.etag {
+                req = req.set("If-None-Match", etag);
+            }
+            if let Some(t) = f.modified {
+                let t = DateTime::<Utc>::from(t).format("%a, %d %b %Y %H:%M:%S GMT");
+                req = req.set("If-Modified-Since", &t.to_string());
+            }
+        } else if partial.written() > 0 {
+            // check what an earlier attempt left against upstream now
+            match self.stat(resource) {
+                Ok(stat) => {
+                    partial.expect(stat);
+                    offset = partial.resume()?;
+                }
+                Err(e) => {
+                    // such as a server which doesn't allow HEAD
+                    debug!("can't stat {resource}, so won't resume it: {:?}", e);
+                    partial.restart()?;
+                }
+            }
+            if offset > 0 {
+                req = req.set("Range", &format!("bytes={offset}-"));
+            }
+        }
+        let resp = match req.call() {
             Ok(resp) => resp,
+            Err(ureq::Error::Status(416, r)) => {
+                partial.restart()?;
+                return Err(GetError::RequestErr(Box::new(ureq::Error::Status(416, r))));
+            }
             Err(e) => return Err(GetError::RequestErr(Box::new(e))),
         };
+        if let Some(f) = since {
+            if resp.status() == 304 || unchanged(f, &resp) {
+                debug!("{} unchanged since fetched", redacted(u.as_str()));
+                return Ok(None);
+            }
+        }
+        if offset > 0 && resp.status() != 206 {
+            debug!("{} sent whole, not from {offset}", redacted(u.as_str()));
+            partial.restart()?;
+            offset = 0;
+        }
+        if offset == 0 {
+            partial.expect(remote_stat(&resp));
+        }
         let mimetype = String::from(resp.content_type());
+        let etag = resp.header("ETag").map(String::from);
         debug!("get with output to {}", output.to_str().unwrap());
 
-        let file = match self.create_output(&output) {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("error on create: {:?}", e);
-                return Err(e);
-            }
-        };
-
         const BUFSIZE: usize = 8192;
         let mut buf: [u8; BUFSIZE] = [0; BUFSIZE];
-        let mut bw = BufWriter::new(file);
+        let mut bw = BufWriter::new(&mut partial);
         let mut r = resp.into_reader();
-        let mut tot: u64 = 0;
-        // keep looping while true
-        while match r.read(&mut buf) {
-            Ok(0) => false,
-            Ok(size) => match bw.write_all(&buf[0..size]) {
-                Ok(_) => {
-                    tot += size as u64;
-                    true
-                }
+        let mut tot: u64 = offset;
+        loop {
+            let size = match r.read(&mut buf) {
+                Ok(0) => break,
+                Ok(size) => size,
                 Err(e) => {
-                    eprintln!("error from write: {:?}", e);
-                    false
+                    warn!("error from read after {} bytes: {:?}", tot, e);
+                    return Err(GetError::ReadError(e));
                 }
-            },
-            Err(e) => {
-                eprintln!("error from read after {} bytes: {:?}", tot, e);
-                false
+            };
+            if let Err(e) = bw.write_all(&buf[0..size]) {
+                warn!("error from write at {} bytes: {:?}", tot, e);
+                return Err(GetError::OutputWriteError(e));
             }
-        } {
-            // no op
+            tot += size as u64;
+        }
+        if let Err(e) = bw.flush() {
+            return Err(GetError::OutputWriteError(e));
+        }
+        drop(bw);
+        debug!("read and wrote {tot} bytes for file {resource}");
+        let mut g = Gotten::new(&mimetype, resource, u, output, tot);
+        g.etag = etag;
+        partial.finish(g).map(Some)
+    }
+}
+
+// whether a full response is the file as fetched before, from a
+// server which ignored the conditions asked of it
+fn unchanged(fetched: &Fetched, resp: &ureq::Response) -> bool {
+    if let (Some(a), Some(b)) = (&fetched.etag, resp.header("ETag")) {
+        return a == b;
+    }
+    let now = remote_stat(resp);
+    now.modified.is_some() && !fetched.differs(&now)
+}
+
+// the size and modification time given in a response's headers
+fn remote_stat(resp: &ureq::Response) -> RemoteStat {
+    let size = resp.header("Content-Length").and_then(|s| s.parse().ok());
+    // HTTP dates are RFC 2822 dates, always in GMT
+    let modified = resp
+        .header("Last-Modified")
+        .and_then(|s| DateTime::parse_from_rfc2822(s).ok())
+        .map(SystemTime::from);
+    RemoteStat { size, modified }
+}
+
+// the files in a directory index page as generated by Apache or nginx,
+// one to a line, such as
+//   <a href="x.png">x.png</a>     14-Oct-2023 15:00    1234
+//   <tr><td><a href="x.png">x.png</a></td><td>2023-10-14 15:00 </td><td>1.2K</td></tr>
+// skipping links to anywhere but a file in the directory itself; the
+// times are taken as UTC, and sizes only when given in bytes
+fn parse_autoindex(dir: &Url, html: &str) -> Vec<(String, RemoteStat)> {
+    let anchor = Regex::new(r#"(?i)<a\s[^>]*href\s*=\s*"([^"]*)"[^>]*>.*?</a>"#).unwrap();
+    let tag = Regex::new(r"<[^>]*>").unwrap();
+    let mut files = Vec::new();
+    for line in html.lines() {
+        let links: Vec<_> = anchor.captures_iter(line).collect();
+        for (i, c) in links.iter().enumerate() {
+            let name = match dir.join(&c[1]) {
+                Ok(u) if u.query().is_none() && u.fragment().is_none() => {
+                    match u.path().strip_prefix(dir.path()) {
+                        Some(n) if !n.is_empty() && !n.contains('/') => {
+                            percent_decode_str(n).decode_utf8_lossy().to_string()
+                        }
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            // what follows the link, up to the next
+            let end = match links.get(i + 1) {
+                Some(next) => next.get(0).unwrap().start(),
+                None => line.len(),
+            };
+            let facts = tag.replace_all(&line[c.get(0).unwrap().end()..end], " ");
+            files.push((name, parse_index_facts(&facts)));
         }
+    }
+    files
+}
 
-        let g = Gotten::new(&mimetype, resource, u, output, tot);
-        Ok(g)
+// the time and size following a link in an index
+fn parse_index_facts(facts: &str) -> RemoteStat {
+    let words: Vec<&str> = facts.split_whitespace().collect();
+    for (i, w) in words.windows(2).enumerate() {
+        let when = format!("{} {}", w[0], w[1]);
+        let t = NaiveDateTime::parse_from_str(&when, "%d-%b-%Y %H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(&when, "%Y-%m-%d %H:%M"));
+        if let Ok(t) = t {
+            return RemoteStat {
+                size: words.get(i + 2).and_then(|s| s.parse().ok()),
+                modified: Some(SystemTime::from(t.and_utc())),
+            };
+        }
     }
+    RemoteStat::default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MediaType;
     use httpmock::prelude::*;
     use std::env;
     use std::fs;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread;
 
     const MOCK_RESOURCE: &str = "README.html";
 
@@ -183,20 +365,330 @@ mod tests {
 
     #[test]
     fn exists() {
-        let m = mock().0;
+        let mut m = mock().0;
         let e = m.exists(MOCK_RESOURCE).unwrap();
         assert!(e, "resource should exist");
         let e = m.exists("asdfasdfasdfafdasfdasdf").unwrap();
         assert!(!e, "resource shouldn't exist");
     }
 
+    #[test]
+    fn stat() {
+        let srv = httpmock::MockServer::start();
+        srv.mock(|when, then| {
+            when.method("HEAD").path("/data.bin");
+            then.status(200)
+                .header("Content-Length", "5")
+                .header("Last-Modified", "Sat, 14 Oct 2023 15:00:00 GMT");
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let s = m.stat("data.bin").unwrap();
+        assert_eq!(Some(5), s.size);
+        let t = DateTime::parse_from_rfc3339("2023-10-14T15:00:00Z").unwrap();
+        assert_eq!(Some(SystemTime::from(t)), s.modified);
+        assert!(matches!(m.stat("nothing.bin"), Err(GetError::NotFound(_))));
+    }
+
+    const NGINX_INDEX: &str = r#"<html>
+<head><title>Index of /GOES/</title></head>
+<body>
+<h1>Index of /GOES/</h1><hr><pre><a href="../">../</a>
+<a href="2023/">2023/</a>                                              14-Oct-2023 15:00                   -
+<a href="OR_ABI_C02.png">OR_ABI_C02.png</a>                                     14-Oct-2023 15:10             1234
+<a href="OR_ABI_C13%20a.png">OR_ABI_C13 a.png</a>                                   14-Oct-2023 15:20             5678
+<a href="latest.png">latest.png</a>                                         14-Oct-2023 15:30            91011
+</pre><hr></body>
+</html>
+"#;
+
+    const APACHE_INDEX: &str = r#"<html><body><h1>Index of /GOES</h1>
+<table>
+<tr><th><a href="?C=N;O=D">Name</a></th><th><a href="?C=M;O=A">Last modified</a></th><th><a href="?C=S;O=A">Size</a></th></tr>
+<tr><td valign="top"><img src="/icons/back.gif" alt="[PARENTDIR]"></td><td><a href="/">Parent Directory</a></td><td>&nbsp;</td><td align="right">  - </td></tr>
+<tr><td valign="top"><img src="/icons/image2.gif" alt="[IMG]"></td><td><a href="OR_ABI_C02.png">OR_ABI_C02.png</a></td><td align="right">2023-10-14 15:10  </td><td align="right">1.2K</td></tr>
+<tr><td valign="top"><img src="/icons/image2.gif" alt="[IMG]"></td><td><a href="http://elsewhere.example.org/x.png">x.png</a></td><td align="right">2023-10-14 15:20  </td><td align="right">5.6K</td></tr>
+</table>
+<address>Apache Server at example.org Port 80</address>
+</body></html>
+"#;
+
+    fn at(h: u32, m: u32) -> Option<SystemTime> {
+        let t = format!("2023-10-14T{h:02}:{m:02}:00Z");
+        Some(SystemTime::from(DateTime::parse_from_rfc3339(&t).unwrap()))
+    }
+
+    #[test]
+    fn autoindex() {
+        let dir = Url::parse("http://example.org/GOES/").unwrap();
+        let files = parse_autoindex(&dir, NGINX_INDEX);
+        assert_eq!(3, files.len());
+        assert_eq!("OR_ABI_C02.png", files[0].0);
+        assert_eq!(Some(1234), files[0].1.size);
+        assert_eq!(at(15, 10), files[0].1.modified);
+        assert_eq!("OR_ABI_C13 a.png", files[1].0);
+
+        let files = parse_autoindex(&dir, APACHE_INDEX);
+        assert_eq!(1, files.len());
+        assert_eq!("OR_ABI_C02.png", files[0].0);
+        assert_eq!(None, files[0].1.size);
+        assert_eq!(at(15, 10), files[0].1.modified);
+    }
+
+    #[test]
+    fn list() {
+        let srv = httpmock::MockServer::start();
+        srv.mock(|when, then| {
+            when.method("GET").path("/GOES/");
+            then.status(200).body(NGINX_INDEX);
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let l = m.list("GOES/OR_").unwrap();
+        assert_eq!(2, l.len());
+        assert_eq!("GOES/OR_ABI_C02.png", l[0].name);
+        assert_eq!("GOES/OR_ABI_C13 a.png", l[1].name);
+        assert_eq!(Some(5678), l[1].size);
+        assert!(m.list("nothing/").unwrap().is_empty());
+
+        let latest = m.latest_matching("GOES/", &|n| n.ends_with(".png"));
+        assert_eq!("GOES/latest.png", latest.unwrap().unwrap().name);
+        assert_eq!(at(15, 30), m.list("GOES/latest").unwrap()[0].modified);
+    }
+
+    // an empty directory under the temporary directory
+    fn output_dir(name: &str) -> PathBuf {
+        let t = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&t);
+        fs::create_dir_all(&t).expect("failed to create temp directory");
+        t
+    }
+
     #[test]
     fn get() {
         let mut m = mock().0;
-        let path = PathBuf::from("/dev/null");
+        let t = output_dir("reflector-http-get-test");
+        let path = t.join("test.bin");
         let got = m.get(MOCK_RESOURCE, path.clone()).unwrap();
         assert_eq!(MOCK_RESOURCE, got.resource);
         assert_eq!(path, got.output);
+        assert_eq!(1, fs::read_dir(&t).unwrap().count(), "only the output");
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    const WHOLE: &str = "not all of it, now!!";
+
+    // leave the first 13 bytes of WHOLE in the partial download of
+    // test.bin in the directory, from a server which promises all 20
+    // before hanging up
+    fn truncated_get(t: &Path) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = [0; 1024];
+            let _ = conn.read(&mut req);
+            let resp = "HTTP/1.1 200 OK\r\nContent-Length: 20\r\n\
+                        Last-Modified: Sat, 14 Oct 2023 15:00:00 GMT\r\n\r\nnot all of it";
+            conn.write_all(resp.as_bytes()).unwrap();
+        });
+        let mut m = Http::from_str(&format!("http://{addr}/")).unwrap();
+        assert!(matches!(
+            m.get("data.bin", t.join("test.bin")),
+            Err(GetError::ReadError(_))
+        ));
+    }
+
+    #[test]
+    fn truncated() {
+        let t = output_dir("reflector-http-truncated-test");
+        truncated_get(&t);
+        assert!(!t.join("test.bin").exists());
+        let kept = fs::read_to_string(t.join(".test.bin.partial")).unwrap();
+        assert_eq!("not all of it", kept, "kept to resume");
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    #[test]
+    fn resume() {
+        let t = output_dir("reflector-http-resume-test");
+        truncated_get(&t);
+        let srv = MockServer::start();
+        srv.mock(|when, then| {
+            when.method("HEAD").path("/data.bin");
+            then.status(200)
+                .header("Content-Length", "20")
+                .header("Last-Modified", "Sat, 14 Oct 2023 15:00:00 GMT");
+        });
+        let rest = srv.mock(|when, then| {
+            when.method(GET)
+                .path("/data.bin")
+                .header("Range", "bytes=13-");
+            then.status(206)
+                .header("Content-Range", "bytes 13-19/20")
+                .body(&WHOLE[13..]);
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let path = t.join("test.bin");
+        let got = m.get("data.bin", path.clone()).unwrap();
+        rest.assert();
+        assert_eq!(20, got.size);
+        assert_eq!(WHOLE, fs::read_to_string(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&t).unwrap().count(), "only the output");
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    #[test]
+    fn restart() {
+        let t = output_dir("reflector-http-restart-test");
+        truncated_get(&t);
+        // changed upstream, to something shorter
+        let srv = MockServer::start();
+        srv.mock(|when, then| {
+            when.method("HEAD").path("/data.bin");
+            then.status(200).header("Content-Length", "5");
+        });
+        srv.mock(|when, then| {
+            when.method(GET).path("/data.bin");
+            then.status(200).body("fresh");
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let path = t.join("test.bin");
+        m.get("data.bin", path.clone()).unwrap();
+        assert_eq!("fresh", fs::read_to_string(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&t).unwrap().count(), "only the output");
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    #[test]
+    fn conditional() {
+        let srv = MockServer::start();
+        srv.mock(|when, then| {
+            when.method(GET)
+                .path("/data.bin")
+                .header("If-None-Match", "\"v1\"");
+            then.status(304);
+        });
+        srv.mock(|when, then| {
+            when.method(GET)
+                .path("/data.bin")
+                .header("If-Modified-Since", "Sat, 14 Oct 2023 15:00:00 GMT");
+            then.status(304);
+        });
+        srv.mock(|when, then| {
+            when.method(GET).path("/data.bin");
+            then.status(200)
+                .header("ETag", "\"v2\"")
+                .header("Last-Modified", "Sat, 14 Oct 2023 16:00:00 GMT")
+                .body("fresh");
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let t = output_dir("reflector-http-conditional-test");
+        let path = t.join("test.bin");
+        let by_etag = Fetched {
+            etag: Some("\"v1\"".to_string()),
+            ..Default::default()
+        };
+        assert!(m
+            .get_if_changed("data.bin", path.clone(), &by_etag)
+            .unwrap()
+            .is_none());
+        let by_time = Fetched {
+            modified: at(15, 0),
+            ..Default::default()
+        };
+        assert!(m
+            .get_if_changed("data.bin", path.clone(), &by_time)
+            .unwrap()
+            .is_none());
+        assert_eq!(0, fs::read_dir(&t).unwrap().count(), "nothing left");
+
+        // as a server ignoring the conditions would answer
+        let same = Fetched {
+            etag: Some("\"v2\"".to_string()),
+            ..Default::default()
+        };
+        assert!(m
+            .get_if_changed("data.bin", path.clone(), &same)
+            .unwrap()
+            .is_none());
+
+        let old = Fetched {
+            etag: Some("\"v0\"".to_string()),
+            ..Default::default()
+        };
+        let got = m
+            .get_if_changed("data.bin", path.clone(), &old)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("\"v2\"".to_string()), got.etag);
+        assert_eq!(at(16, 0), got.remote_modified);
+        assert_eq!("fresh", fs::read_to_string(&path).unwrap());
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    #[test]
+    fn no_head() {
+        let t = output_dir("reflector-http-no-head-test");
+        truncated_get(&t);
+        let srv = MockServer::start();
+        srv.mock(|when, then| {
+            when.method("HEAD").path("/data.bin");
+            then.status(405);
+        });
+        let whole = srv.mock(|when, then| {
+            when.method(GET)
+                .path("/data.bin")
+                .matches(|req| !req.headers.iter().flatten().any(|(k, _)| k == "range"));
+            then.status(200).body(WHOLE);
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let path = t.join("test.bin");
+        m.get("data.bin", path.clone()).unwrap();
+        whole.assert();
+        assert_eq!(WHOLE, fs::read_to_string(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&t).unwrap().count(), "only the output");
+        fs::remove_dir_all(&t).unwrap();
+    }
+
+    #[test]
+    fn media_types() {
+        const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let srv = MockServer::start();
+        srv.mock(|when, then| {
+            when.method(GET).path("/error.png");
+            then.status(200)
+                .header("Content-Type", "text/html; charset=utf-8")
+                .body("<html>not here</html>");
+        });
+        srv.mock(|when, then| {
+            when.method(GET).path("/html.png");
+            then.status(200)
+                .header("Content-Type", "application/octet-stream")
+                .body("<html>not here</html>");
+        });
+        srv.mock(|when, then| {
+            when.method(GET).path("/real.png");
+            then.status(200)
+                .header("Content-Type", "image/png")
+                .body(PNG);
+        });
+        let mut m = Http::from_str(&srv.base_url()).unwrap();
+        let t = output_dir("reflector-http-media-test");
+        assert!(matches!(
+            m.get("error.png", t.join("error.png")),
+            Err(GetError::InvalidOutput(
+                GottenValidation::UnexpectedContentType(_, MediaType::Png)
+            ))
+        ));
+        assert!(matches!(
+            m.get("html.png", t.join("html.png")),
+            Err(GetError::InvalidOutput(GottenValidation::NotOfMediaType(
+                MediaType::Png
+            )))
+        ));
+        assert_eq!(0, fs::read_dir(&t).unwrap().count(), "nothing left");
+        let got = m.get("real.png", t.join("real.png")).unwrap();
+        assert_eq!(Some(PNG.len() as u64), got.remote_size);
+        fs::remove_dir_all(&t).unwrap();
     }
 
     #[test]
@@ -213,8 +705,10 @@ mod tests {
 
     #[test]
     fn not_found() {
-        let path = PathBuf::from("/dev/null");
-        let fail = mock().0.get("asdfasfdasfd", path);
-        assert!(fail.is_err())
+        let t = output_dir("reflector-http-not-found-test");
+        let fail = mock().0.get("asdfasfdasfd", t.join("test.bin"));
+        assert!(fail.is_err());
+        assert_eq!(0, fs::read_dir(&t).unwrap().count(), "nothing left");
+        fs::remove_dir_all(&t).unwrap();
     }
 }
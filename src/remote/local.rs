@@ -0,0 +1,208 @@
+// a local directory tree treated as the remote site, for file:// URLs,
+// e.g. data staged on an NFS share or removable media
+
+use super::*;
+use log::debug;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use url::Url;
+
+pub struct Local {
+    pub base: Url,
+    pub root: PathBuf,
+}
+
+impl Local {
+    pub fn new(base: &Url) -> Result<Local, ConnectError> {
+        let root = match base.to_file_path() {
+            Ok(p) => p,
+            Err(_) => return Err(ConnectError::NotALocalPath(base.to_string())),
+        };
+        // so that resources join beneath the root, as with Http
+        let base = match base.path().ends_with('/') {
+            true => base.clone(),
+            false => {
+                let mut b = base.clone();
+                b.set_path(&format!("{}/", base.path()));
+                b
+            }
+        };
+        Ok(Local { base, root })
+    }
+
+    fn path(&self, resource: &str) -> PathBuf {
+        self.root.join(resource)
+    }
+}
+
+impl RemoteClient for Local {
+    fn ping(&mut self) -> Result<Duration, PingError> {
+        let start = Instant::now();
+        match fs::metadata(&self.root) {
+            Ok(m) if m.is_dir() => Ok(start.elapsed()),
+            Ok(_) => Err(PingError::NotADirectory(self.root.clone())),
+            Err(e) => Err(PingError::LocalIOError(e)),
+        }
+    }
+
+    fn url(&self, resource: &str) -> Result<Url, GetError> {
+        match self.base.join(resource) {
+            Ok(u) => Ok(u),
+            Err(e) => Err(GetError::UnparsableURL(e)),
+        }
+    }
+
+    fn exists(&mut self, resource: &str) -> Result<bool, GetError> {
+        Ok(self.path(resource).is_file())
+    }
+
+    fn stat(&mut self, resource: &str) -> Result<RemoteStat, GetError> {
+        match fs::metadata(self.path(resource)) {
+            Ok(m) if m.is_file() => Ok(RemoteStat {
+                size: Some(m.len()),
+                modified: m.modified().ok(),
+            }),
+            Ok(_) => Err(GetError::NotFound(resource.to_string())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(GetError::NotFound(resource.to_string()))
+            }
+            Err(e) => Err(GetError::LocalIOError(e)),
+        }
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<RemoteEntry>, ListError> {
+        let dir = match fs::read_dir(self.path(split_prefix(prefix).0)) {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(ListError::LocalReadDirError(e)),
+        };
+        let mut files = Vec::new();
+        for entry in dir {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => return Err(ListError::LocalReadDirError(e)),
+            };
+            let m = match entry.metadata() {
+                Ok(m) if m.is_file() => m,
+                Ok(_) => continue,
+                Err(e) => return Err(ListError::LocalReadDirError(e)),
+            };
+            let stat = RemoteStat {
+                size: Some(m.len()),
+                modified: m.modified().ok(),
+            };
+            files.push((entry.file_name().to_string_lossy().to_string(), stat));
+        }
+        Ok(select_entries(prefix, files))
+    }
+
+    fn get(&mut self, resource: &str, output: PathBuf) -> Result<Gotten, GetError> {
+        let source = self.url(resource)?;
+        let mut input = match fs::File::open(self.path(resource)) {
+            Ok(f) => f,
+            Err(e) => return Err(GetError::LocalIOError(e)),
+        };
+        let remote = match input.metadata() {
+            Ok(m) => RemoteStat {
+                size: Some(m.len()),
+                modified: m.modified().ok(),
+            },
+            Err(_) => RemoteStat::default(),
+        };
+        let mut partial = self.create_output(&output)?;
+        let mut bw = BufWriter::new(&mut partial);
+        let tot = match io::copy(&mut input, &mut bw) {
+            Ok(n) => n,
+            Err(e) => return Err(GetError::LocalIOError(e)),
+        };
+        if let Err(e) = bw.flush() {
+            return Err(GetError::LocalIOError(e));
+        }
+        drop(bw);
+        debug!("copied {tot} bytes for file {resource}");
+        let g = Gotten::new("application/octet-stream", resource, source, output, tot);
+        partial.finish(g.with_remote(remote))
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // an upstream directory holding a single file
+    fn mock(name: &str) -> Local {
+        let dir = env::temp_dir().join(name);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/data.txt"), "upstream data").unwrap();
+        Local::new(&Url::from_directory_path(&dir).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn new() {
+        let m = Local::new(&Url::parse("file:///srv/data").unwrap()).unwrap();
+        assert_eq!(PathBuf::from("/srv/data"), m.root);
+        assert_eq!("file:///srv/data/x.png", m.url("x.png").unwrap().as_str());
+        assert!(Local::new(&Url::parse("file://host/srv").unwrap()).is_err());
+    }
+
+    #[test]
+    fn ping() {
+        let mut m = mock("reflector-local-ping-test");
+        m.ping().unwrap();
+        let mut gone = Local::new(&Url::parse("file:///nonexistent/dir/").unwrap()).unwrap();
+        assert!(gone.ping().is_err());
+    }
+
+    #[test]
+    fn exists() {
+        let mut m = mock("reflector-local-exists-test");
+        assert!(m.exists("sub/data.txt").unwrap());
+        assert!(!m.exists("sub/nothing.txt").unwrap());
+        assert!(!m.exists("sub").unwrap());
+    }
+
+    #[test]
+    fn stat() {
+        let mut m = mock("reflector-local-stat-test");
+        let s = m.stat("sub/data.txt").unwrap();
+        assert_eq!(Some(13), s.size);
+        assert!(s.modified.is_some());
+        assert!(matches!(m.stat("sub"), Err(GetError::NotFound(_))));
+        assert!(matches!(m.stat("nothing"), Err(GetError::NotFound(_))));
+    }
+
+    #[test]
+    fn get() {
+        let mut m = mock("reflector-local-get-test");
+        let out = env::temp_dir().join("reflector-local-get-test.out");
+        let _ = fs::remove_file(&out);
+        let got = m.get("sub/data.txt", out.clone()).unwrap();
+        got.validate().unwrap();
+        assert_eq!(13, got.size);
+        assert_eq!("upstream data", fs::read_to_string(&out).unwrap());
+        assert!(m.get("sub/nothing.txt", out.clone()).is_err());
+        fs::remove_file(&out).unwrap();
+    }
+
+    #[test]
+    fn list() {
+        let mut m = mock("reflector-local-list-test");
+        assert!(m.list("").unwrap().is_empty(), "directories aren't listed");
+        let l = m.list("sub/").unwrap();
+        assert_eq!(1, l.len());
+        assert_eq!("sub/data.txt", l[0].name);
+        assert_eq!(Some(13), l[0].size);
+        assert!(m.list("sub/x").unwrap().is_empty());
+        assert!(m.list("nothing/").unwrap().is_empty());
+        let latest = m.latest_matching("sub/", &|n| n.ends_with(".txt"));
+        assert_eq!("sub/data.txt", latest.unwrap().unwrap().name);
+    }
+}
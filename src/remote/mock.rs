@@ -25,12 +25,19 @@ impl RemoteClient for Mock {
         Ok(Gotten::new("x-raw/mock", resource, source, output, 0))
     }
 
-    fn exists(&self, _resource: &str) -> Result<bool, GetError> {
+    fn exists(&mut self, _resource: &str) -> Result<bool, GetError> {
         Ok(true)
     }
 
-    fn remote_addr(&self) -> SocketAddr {
-        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 6666)
+    fn stat(&mut self, _resource: &str) -> Result<RemoteStat, GetError> {
+        Ok(RemoteStat::default())
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(SocketAddr::new(
+            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            6666,
+        ))
     }
 }
 
@@ -0,0 +1,152 @@
+// a minimal reader for ~/.netrc, as used by ftp(1) and curl, e.g.
+//   machine ftp.example.org login reflector password s3cret
+//   default login anonymous password reflector@example.org
+// macdef bodies are skipped, and account entries ignored
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct NetrcEntry {
+    pub login: Option<String>,
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for NetrcEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetrcEntry")
+            .field("login", &self.login)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Netrc {
+    pub machines: Vec<(String, NetrcEntry)>,
+    pub default: Option<NetrcEntry>,
+}
+
+impl Netrc {
+    pub fn parse(s: &str) -> Netrc {
+        let mut n = Netrc::default();
+        // the entry being read: None for the default
+        let mut current: Option<(Option<String>, NetrcEntry)> = None;
+        let mut lines = s.lines();
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+            while let Some(t) = tokens.next() {
+                match t {
+                    "machine" | "default" => {
+                        n.finish(current.take());
+                        let name = match t {
+                            "machine" => tokens.next().map(String::from),
+                            _ => None,
+                        };
+                        current = Some((name, NetrcEntry::default()));
+                    }
+                    "login" | "password" => {
+                        let v = tokens.next().map(String::from);
+                        if let Some((_, e)) = current.as_mut() {
+                            match t {
+                                "login" => e.login = v,
+                                _ => e.password = v,
+                            }
+                        }
+                    }
+                    "account" => {
+                        tokens.next();
+                    }
+                    "macdef" => {
+                        // the macro runs to the next blank line
+                        for l in lines.by_ref() {
+                            if l.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        break;
+                    }
+                    _ if t.starts_with('#') => break,
+                    _ => (),
+                }
+            }
+        }
+        n.finish(current);
+        n
+    }
+
+    fn finish(&mut self, entry: Option<(Option<String>, NetrcEntry)>) {
+        match entry {
+            Some((Some(m), e)) => self.machines.push((m, e)),
+            Some((None, e)) => self.default = Some(e),
+            None => (),
+        }
+    }
+
+    /// read $NETRC, or else ~/.netrc, if it exists
+    pub fn load() -> Option<Netrc> {
+        let path = match env::var_os("NETRC") {
+            Some(p) => PathBuf::from(p),
+            None => PathBuf::from(env::var_os("HOME")?).join(".netrc"),
+        };
+        match fs::read_to_string(path) {
+            Ok(s) => Some(Netrc::parse(&s)),
+            Err(_) => None,
+        }
+    }
+
+    /// the entry for a host, falling back to the default
+    pub fn find(&self, host: &str) -> Option<&NetrcEntry> {
+        match self
+            .machines
+            .iter()
+            .find(|(m, _)| m.eq_ignore_ascii_case(host))
+        {
+            Some((_, e)) => Some(e),
+            None => self.default.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETRC: &str = "
+# mirrors
+machine ftp.example.org
+    login reflector
+    password s3cret
+machine other.example.org login someone account x password pw
+
+macdef init
+cd /pub
+binary
+
+default login anonymous password contact@example.org
+";
+
+    #[test]
+    fn parse() {
+        let n = Netrc::parse(NETRC);
+        assert_eq!(2, n.machines.len());
+        let e = n.find("FTP.example.org").unwrap();
+        assert_eq!(Some("reflector".to_string()), e.login);
+        assert_eq!(Some("s3cret".to_string()), e.password);
+        let e = n.find("other.example.org").unwrap();
+        assert_eq!(Some("pw".to_string()), e.password);
+        let e = n.find("elsewhere.example.org").unwrap();
+        assert_eq!(Some("anonymous".to_string()), e.login);
+        assert!(Netrc::parse("").find("ftp.example.org").is_none());
+    }
+
+    #[test]
+    fn redacted() {
+        let n = Netrc::parse(NETRC);
+        let d = format!("{n:?}");
+        assert!(d.contains("reflector"));
+        assert!(!d.contains("s3cret"));
+    }
+}
@@ -0,0 +1,107 @@
+// per-source options for a remote client, as read from the
+// configuration, e.g.
+//   [sources.remote_options]
+//   user = "reflector"
+//   key  = "/etc/reflector/id_ed25519"
+// each client checks for the options it understands
+
+use super::RCFactoryError::{self, InvalidOption, UnknownOption};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use toml::Value;
+
+// options whose values are never shown
+const SECRET: [&str; 2] = ["password", "passphrase"];
+
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct RemoteOptions {
+    pub params: BTreeMap<String, Value>,
+}
+
+impl RemoteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a string option, builder style
+    pub fn with(mut self, name: &str, value: &str) -> Self {
+        self.params
+            .insert(name.to_string(), Value::String(value.to_string()));
+        self
+    }
+
+    /// fetch an optional string option
+    pub fn str_param(&self, name: &str) -> Result<Option<&str>, RCFactoryError> {
+        match self.params.get(name) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(v) => Err(self.invalid(name, &format!("expected a string, not {}", v.type_str()))),
+        }
+    }
+
+    /// fetch an optional boolean option
+    pub fn bool_param(&self, name: &str) -> Result<Option<bool>, RCFactoryError> {
+        match self.params.get(name) {
+            None => Ok(None),
+            Some(Value::Boolean(b)) => Ok(Some(*b)),
+            Some(v) => {
+                Err(self.invalid(name, &format!("expected a boolean, not {}", v.type_str())))
+            }
+        }
+    }
+
+    /// fail on any option not in the list of those known
+    pub fn only_params(&self, known: &[&str]) -> Result<(), RCFactoryError> {
+        match self.params.keys().find(|k| !known.contains(&k.as_str())) {
+            Some(k) => Err(UnknownOption(k.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// an error for the named option
+    pub fn invalid(&self, name: &str, why: &str) -> RCFactoryError {
+        InvalidOption(name.to_string(), why.to_string())
+    }
+}
+
+impl fmt::Debug for RemoteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut m = f.debug_map();
+        for (k, v) in self.params.iter() {
+            match SECRET.contains(&k.as_str()) {
+                true => m.entry(k, &"<redacted>"),
+                false => m.entry(k, v),
+            };
+        }
+        m.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params() {
+        let o: RemoteOptions = toml::from_str("user = \"me\"\nport = 22\nsafe = true").unwrap();
+        assert_eq!(Some("me"), o.str_param("user").unwrap());
+        assert_eq!(None, o.str_param("key").unwrap());
+        assert!(matches!(o.str_param("port"), Err(InvalidOption(..))));
+        assert_eq!(Some(true), o.bool_param("safe").unwrap());
+        assert!(matches!(o.bool_param("user"), Err(InvalidOption(..))));
+        assert!(matches!(o.only_params(&["user"]), Err(UnknownOption(_))));
+        o.only_params(&["user", "port", "safe"]).unwrap();
+    }
+
+    #[test]
+    fn redacted() {
+        let o = RemoteOptions::new()
+            .with("user", "me")
+            .with("password", "hunter2");
+        let d = format!("{o:?}");
+        assert!(d.contains("me"));
+        assert!(!d.contains("hunter2"));
+    }
+}
@@ -0,0 +1,328 @@
+// a download in progress: written to a hidden sibling of its output,
+// and only renamed into place once complete and valid, so a capture
+// in the store is never half a file.  Dropped unfinished, the sibling
+// is removed, unless it's the start of a file whose size upstream is
+// known, in which case it's kept, stamped with the upstream
+// modification time, for the next attempt to resume
+
+use super::{GetError, Gotten, RemoteStat};
+use log::{debug, info, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+pub struct Partial {
+    pub output: PathBuf,
+    pub temp: PathBuf,
+    file: File,
+    expected: RemoteStat, // of the file upstream
+    started: bool,        // whether what's written is this attempt's
+    finished: bool,
+}
+
+impl Partial {
+    /// open the sibling of the output, keeping whatever an earlier
+    /// attempt left there until resume or restart
+    pub fn create(output: &Path) -> Result<Partial, GetError> {
+        if output.is_dir() {
+            return Err(GetError::OutputExistsAsDir(output.to_path_buf()));
+        }
+        if output.is_file() {
+            return Err(GetError::OutputFileExists(output.to_path_buf()));
+        }
+        let temp = match output.file_name() {
+            Some(n) => output.with_file_name(format!(".{}.partial", n.to_string_lossy())),
+            None => return Err(GetError::OutputExistsAsDir(output.to_path_buf())),
+        };
+        let opened = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&temp);
+        match opened {
+            Ok(file) => Ok(Partial {
+                output: output.to_path_buf(),
+                temp,
+                file,
+                expected: RemoteStat::default(),
+                started: false,
+                finished: false,
+            }),
+            Err(e) => Err(GetError::OutputCreateFile(e)),
+        }
+    }
+
+    /// what upstream says of the file being downloaded, which
+    /// resuming and finishing are checked against
+    pub fn expect(&mut self, stat: RemoteStat) {
+        self.expected = stat;
+    }
+
+    /// the bytes written so far
+    pub fn written(&self) -> u64 {
+        self.file.metadata().map(|m| m.len()).unwrap_or(0)
+    }
+
+    // whether what's written is the start of the expected file: shorter
+    // than it, and written by this attempt or stamped with its
+    // modification time; with no modification time to go by, what an
+    // earlier attempt left can't be told from the start of another file
+    fn resumable(&self) -> bool {
+        let written = self.written();
+        if written == 0 || self.expected.size.is_none_or(|s| written >= s) {
+            return false;
+        }
+        match (self.started, self.expected.modified) {
+            (true, _) => true,
+            (false, None) => false,
+            (false, Some(t)) => self.file.metadata().and_then(|m| m.modified()).ok() == Some(t),
+        }
+    }
+
+    /// where to resume the download from: the end of what's written,
+    /// if it can be trusted, or else 0, having discarded it
+    pub fn resume(&mut self) -> Result<u64, GetError> {
+        if !self.resumable() {
+            self.restart()?;
+            return Ok(0);
+        }
+        match self.file.seek(SeekFrom::End(0)) {
+            Ok(offset) => {
+                info!("resuming download to {} at {offset}", self.output.display());
+                self.started = true;
+                Ok(offset)
+            }
+            Err(e) => Err(GetError::OutputWriteError(e)),
+        }
+    }
+
+    /// discard what's written, to download from the start
+    pub fn restart(&mut self) -> Result<(), GetError> {
+        match self.truncate() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GetError::OutputWriteError(e)),
+        }
+    }
+
+    fn truncate(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.rewind()?;
+        self.started = true;
+        Ok(())
+    }
+
+    /// validate what was written against the Gotten, and the size
+    /// expected, and move it into place, giving the Gotten for the
+    /// final output
+    pub fn finish(mut self, mut gotten: Gotten) -> Result<Gotten, GetError> {
+        if let Err(e) = self.file.flush() {
+            return Err(GetError::OutputWriteError(e));
+        }
+        // whatever happens now, there's nothing to resume
+        let expected = self.expected.size.take();
+        gotten.remote_size = gotten.remote_size.or(expected);
+        gotten.remote_modified = gotten.remote_modified.or(self.expected.modified);
+        gotten.output = self.temp.clone();
+        if let Err(v) = gotten.validate() {
+            warn!("invalid download of {}: {:?}", gotten.resource, v);
+            return Err(GetError::InvalidOutput(v));
+        }
+        // another fetch may have got there first
+        if self.output.exists() {
+            return Err(GetError::OutputFileExists(self.output.clone()));
+        }
+        if let Err(e) = fs::rename(&self.temp, &self.output) {
+            return Err(GetError::OutputRenameError(e));
+        }
+        self.finished = true;
+        gotten.output = self.output.clone();
+        Ok(gotten)
+    }
+}
+
+// writing without resuming or restarting first starts afresh
+impl Write for Partial {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.started {
+            self.truncate()?;
+        }
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // left untouched, it's still what an earlier attempt left; with
+        // no modification time, a later attempt couldn't trust it
+        let keep = match self.started {
+            true => self.resumable() && self.expected.modified.is_some(),
+            false => self.written() > 0,
+        };
+        if keep {
+            if let (true, Some(t)) = (self.started, self.expected.modified) {
+                if let Err(e) = self.file.set_modified(t) {
+                    warn!("can't stamp partial download {}: {e}", self.temp.display());
+                }
+            }
+            debug!("keeping partial download {}", self.temp.display());
+            return;
+        }
+        match fs::remove_file(&self.temp) {
+            Ok(_) => debug!("removed partial download {}", self.temp.display()),
+            Err(e) => warn!("can't remove partial download {}: {e}", self.temp.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::{Duration, SystemTime};
+    use url::Url;
+
+    fn dir(name: &str) -> PathBuf {
+        let d = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&d);
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    fn gotten(size: u64) -> Gotten {
+        let source = Url::parse("http://127.0.0.1/x.bin").unwrap();
+        Gotten::new("x-raw/test", "x.bin", source, PathBuf::new(), size)
+    }
+
+    #[test]
+    fn finish() {
+        let d = dir("reflector-partial-finish-test");
+        let out = d.join("x.bin");
+        let mut p = Partial::create(&out).unwrap();
+        assert_eq!(d.join(".x.bin.partial"), p.temp);
+        p.write_all(b"12345").unwrap();
+        assert!(!out.exists(), "nothing in place before finishing");
+        let got = p.finish(gotten(5)).unwrap();
+        assert_eq!(out, got.output);
+        assert_eq!("12345", fs::read_to_string(&out).unwrap());
+        assert!(!d.join(".x.bin.partial").exists());
+        assert!(matches!(
+            Partial::create(&out),
+            Err(GetError::OutputFileExists(_))
+        ));
+        fs::remove_dir_all(&d).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        let d = dir("reflector-partial-invalid-test");
+        let out = d.join("x.bin");
+        let mut p = Partial::create(&out).unwrap();
+        p.write_all(b"123").unwrap();
+        assert!(matches!(
+            p.finish(gotten(5)),
+            Err(GetError::InvalidOutput(_))
+        ));
+        assert_eq!(0, fs::read_dir(&d).unwrap().count(), "nothing left");
+        fs::remove_dir_all(&d).unwrap();
+    }
+
+    fn stat(size: u64, modified: SystemTime) -> RemoteStat {
+        RemoteStat {
+            size: Some(size),
+            modified: Some(modified),
+        }
+    }
+
+    #[test]
+    fn resume() {
+        let d = dir("reflector-partial-resume-test");
+        let out = d.join("x.bin");
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1_697_295_600);
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(stat(5, then));
+        assert_eq!(0, p.resume().unwrap());
+        p.write_all(b"123").unwrap();
+        drop(p);
+        let temp = d.join(".x.bin.partial");
+        assert_eq!(then, fs::metadata(&temp).unwrap().modified().unwrap());
+
+        // left alone by a failure before resuming
+        drop(Partial::create(&out).unwrap());
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(stat(5, then));
+        assert_eq!(3, p.resume().unwrap());
+        p.write_all(b"45").unwrap();
+        p.finish(gotten(5)).unwrap();
+        assert_eq!("12345", fs::read_to_string(&out).unwrap());
+        fs::remove_dir_all(&d).unwrap();
+    }
+
+    #[test]
+    fn changed_upstream() {
+        let d = dir("reflector-partial-changed-test");
+        let out = d.join("x.bin");
+        let then = SystemTime::UNIX_EPOCH + Duration::from_secs(1_697_295_600);
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(stat(5, then));
+        p.write_all(b"123").unwrap();
+        drop(p);
+
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(stat(5, then + Duration::from_secs(60)));
+        assert_eq!(0, p.resume().unwrap(), "modified since");
+        p.write_all(b"abc").unwrap();
+        drop(p);
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(stat(3, then + Duration::from_secs(60)));
+        assert_eq!(0, p.resume().unwrap(), "no shorter than upstream");
+        drop(p);
+        assert_eq!(0, fs::read_dir(&d).unwrap().count(), "nothing left");
+        fs::remove_dir_all(&d).unwrap();
+    }
+
+    #[test]
+    fn unknown_modification() {
+        let d = dir("reflector-partial-unknown-test");
+        let out = d.join("x.bin");
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(RemoteStat {
+            size: Some(5),
+            modified: None,
+        });
+        p.write_all(b"123").unwrap();
+        assert_eq!(3, p.resume().unwrap(), "written by this attempt");
+        drop(p);
+        assert_eq!(0, fs::read_dir(&d).unwrap().count(), "not kept");
+
+        // perhaps replaced upstream by a larger file since
+        fs::write(d.join(".x.bin.partial"), "123").unwrap();
+        let mut p = Partial::create(&out).unwrap();
+        p.expect(RemoteStat {
+            size: Some(8),
+            modified: None,
+        });
+        assert_eq!(0, p.resume().unwrap(), "left by an earlier attempt");
+        drop(p);
+        assert_eq!(0, fs::read_dir(&d).unwrap().count(), "nothing left");
+        fs::remove_dir_all(&d).unwrap();
+    }
+
+    #[test]
+    fn abandoned() {
+        let d = dir("reflector-partial-abandoned-test");
+        let out = d.join("x.bin");
+        let mut p = Partial::create(&out).unwrap();
+        p.write_all(b"123").unwrap();
+        drop(p);
+        assert_eq!(0, fs::read_dir(&d).unwrap().count(), "nothing left");
+        fs::remove_dir_all(&d).unwrap();
+    }
+}
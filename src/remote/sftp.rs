@@ -0,0 +1,369 @@
+// SFTP remote client, for sites which only offer SSH access, which
+// connects when first used
+//
+// the login comes from the source's remote options:
+//   user        - unless given in the URL, as in sftp://user@host/dir
+//   key         - path of a private key file, with optional passphrase
+//   password    - used when no key is given
+//   known_hosts - OpenSSH known_hosts file used to verify the server,
+//                 by default ~/.ssh/known_hosts; an unknown host or
+//                 a mismatched key fails the connection
+//   insecure_skip_host_key - true to accept any host key instead
+// with neither key nor password, the ssh agent is tried
+
+use super::*;
+use log::{debug, warn};
+use ssh2::{CheckResult, ErrorCode, KnownHostFileKind, Session};
+use std::io::{self, BufWriter, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::{env, fmt};
+use url::Url;
+
+// SSH_FX_NO_SUCH_FILE
+const NO_SUCH_FILE: ErrorCode = ErrorCode::SFTP(2);
+
+#[derive(Clone)]
+pub enum SftpAuth {
+    Key(PathBuf, Option<String>), // private key file, passphrase
+    Password(String),
+    Agent,
+}
+
+impl fmt::Debug for SftpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SftpAuth::Key(k, _) => write!(f, "Key({})", k.display()),
+            SftpAuth::Password(_) => write!(f, "Password(<redacted>)"),
+            SftpAuth::Agent => write!(f, "Agent"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SftpCredentials {
+    pub user: String,
+    pub auth: SftpAuth,
+    pub known_hosts: PathBuf,
+    pub insecure_skip_host_key: bool,
+}
+
+impl TryFrom<(&Url, &RemoteOptions)> for SftpCredentials {
+    type Error = RCFactoryError;
+
+    fn try_from((url, opts): (&Url, &RemoteOptions)) -> Result<Self, Self::Error> {
+        opts.only_params(&[
+            "user",
+            "key",
+            "passphrase",
+            "password",
+            "known_hosts",
+            "insecure_skip_host_key",
+        ])?;
+        let user = match (url.username(), opts.str_param("user")?) {
+            ("", Some(u)) => u.to_string(),
+            ("", None) => return Err(RCFactoryError::MissingOption("user".to_string())),
+            (u, _) => u.to_string(),
+        };
+        let passphrase = opts.str_param("passphrase")?.map(String::from);
+        let auth = match (opts.str_param("key")?, opts.str_param("password")?) {
+            (Some(_), Some(_)) => return Err(opts.invalid("password", "conflicts with key")),
+            (Some(k), None) => SftpAuth::Key(PathBuf::from(k), passphrase),
+            (None, Some(p)) => SftpAuth::Password(p.to_string()),
+            (None, None) => SftpAuth::Agent,
+        };
+        let known_hosts = match opts.str_param("known_hosts")? {
+            Some(kh) => PathBuf::from(kh),
+            None => match env::var_os("HOME") {
+                Some(h) => PathBuf::from(h).join(".ssh/known_hosts"),
+                None => return Err(RCFactoryError::MissingOption("known_hosts".to_string())),
+            },
+        };
+        let insecure_skip_host_key = opts.bool_param("insecure_skip_host_key")?.unwrap_or(false);
+        Ok(SftpCredentials {
+            user,
+            auth,
+            known_hosts,
+            insecure_skip_host_key,
+        })
+    }
+}
+
+// an open sftp channel
+struct Connection {
+    sftp: ssh2::Sftp,
+    _session: Session, // kept for the life of the channel
+}
+
+pub struct Sftp {
+    pub base: Url,
+    pub dir: PathBuf,
+    pub creds: SftpCredentials,
+    conn: Option<Connection>,
+    remote: Option<SocketAddr>,
+}
+
+fn check_host_key(
+    session: &Session,
+    known_hosts: &Path,
+    base: &Url,
+    port: u16,
+) -> Result<(), ConnectError> {
+    let host = base.host_str().unwrap_or("");
+    let mut kh = match session.known_hosts() {
+        Ok(kh) => kh,
+        Err(e) => return Err(ConnectError::SshHandshakeErr(e)),
+    };
+    if let Err(e) = kh.read_file(known_hosts, KnownHostFileKind::OpenSSH) {
+        return Err(ConnectError::SshHandshakeErr(e));
+    }
+    let key = match session.host_key() {
+        Some((k, _)) => k,
+        None => return Err(ConnectError::SshHostKeyErr(host.to_string())),
+    };
+    match kh.check_port(host, port, key) {
+        CheckResult::Match => Ok(()),
+        _ => Err(ConnectError::SshHostKeyErr(host.to_string())),
+    }
+}
+
+fn connect(
+    remote: SocketAddr,
+    base: &Url,
+    dir: &Path,
+    creds: &SftpCredentials,
+) -> Result<Connection, ConnectError> {
+    let tcp = match TcpStream::connect_timeout(&remote, Duration::new(10, 0)) {
+        Ok(s) => s,
+        Err(e) => return Err(ConnectError::SocketError(e)),
+    };
+    let mut session = match Session::new() {
+        Ok(s) => s,
+        Err(e) => return Err(ConnectError::SshHandshakeErr(e)),
+    };
+    session.set_tcp_stream(tcp);
+    session.set_timeout(300 * 1000);
+    if let Err(e) = session.handshake() {
+        return Err(ConnectError::SshHandshakeErr(e));
+    }
+    match creds.insecure_skip_host_key {
+        true => warn!("not verifying the host key for {}, as configured", remote),
+        false => check_host_key(&session, &creds.known_hosts, base, remote.port())?,
+    }
+
+    let user = creds.user.as_str();
+    let auth = match &creds.auth {
+        SftpAuth::Key(k, p) => session.userauth_pubkey_file(user, None, k, p.as_deref()),
+        SftpAuth::Password(p) => session.userauth_password(user, p),
+        SftpAuth::Agent => session.userauth_agent(user),
+    };
+    if let Err(e) = auth {
+        return Err(ConnectError::SshLoginErr(e));
+    }
+
+    let sftp = match session.sftp() {
+        Ok(s) => s,
+        Err(e) => return Err(ConnectError::SftpInitErr(e)),
+    };
+    match sftp.stat(dir) {
+        Ok(s) if s.is_dir() => (),
+        Ok(_) => return Err(ConnectError::SftpCwdErr(None)),
+        Err(e) => return Err(ConnectError::SftpCwdErr(Some(e))),
+    }
+    Ok(Connection {
+        sftp,
+        _session: session,
+    })
+}
+
+impl Sftp {
+    pub fn new(base: &Url, creds: SftpCredentials) -> Sftp {
+        // so that resources join beneath the base, as with Http
+        let base = match base.path().ends_with('/') {
+            true => base.clone(),
+            false => {
+                let mut b = base.clone();
+                b.set_path(&format!("{}/", base.path()));
+                b
+            }
+        };
+        let dir = PathBuf::from(base.path());
+        Sftp {
+            base,
+            dir,
+            creds,
+            conn: None,
+            remote: None,
+        }
+    }
+
+    // the sftp channel, connecting and logging in if not yet done
+    fn sftp(&mut self) -> Result<&ssh2::Sftp, ConnectError> {
+        if self.conn.is_none() {
+            let remote = match self.base.socket_addrs(|| Some(22)) {
+                Ok(a) if !a.is_empty() => a[0],
+                Ok(_) => return Err(ConnectError::UnknownErr),
+                Err(e) => return Err(ConnectError::SocketError(e)),
+            };
+            self.remote = Some(remote);
+            self.conn = Some(connect(remote, &self.base, &self.dir, &self.creds)?);
+        }
+        match &self.conn {
+            Some(c) => Ok(&c.sftp),
+            None => Err(ConnectError::UnknownErr),
+        }
+    }
+
+    fn path(&self, resource: &str) -> PathBuf {
+        self.dir.join(resource)
+    }
+}
+
+impl RemoteClient for Sftp {
+    fn ping(&mut self) -> Result<Duration, PingError> {
+        let start = Instant::now();
+        let dir = self.dir.clone();
+        match self.sftp()?.stat(&dir) {
+            Ok(_) => Ok(start.elapsed()),
+            Err(e) => Err(PingError::SftpStatError(e)),
+        }
+    }
+
+    fn url(&self, resource: &str) -> Result<Url, GetError> {
+        match self.base.join(resource) {
+            Ok(u) => Ok(u),
+            Err(e) => Err(GetError::UnparsableURL(e)),
+        }
+    }
+
+    fn exists(&mut self, resource: &str) -> Result<bool, GetError> {
+        match self.stat(resource) {
+            Ok(_) => Ok(true),
+            Err(GetError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn stat(&mut self, resource: &str) -> Result<RemoteStat, GetError> {
+        let path = self.path(resource);
+        match self.sftp()?.stat(&path) {
+            Ok(s) if s.is_file() => Ok(RemoteStat {
+                size: s.size,
+                modified: s.mtime.map(|t| UNIX_EPOCH + Duration::from_secs(t)),
+            }),
+            Ok(_) => Err(GetError::NotFound(resource.to_string())),
+            Err(e) if e.code() == NO_SUCH_FILE => Err(GetError::NotFound(resource.to_string())),
+            Err(e) => Err(GetError::RetrieveSftpError(e)),
+        }
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<RemoteEntry>, ListError> {
+        let dir = self.path(split_prefix(prefix).0);
+        let entries = match self.sftp()?.readdir(dir) {
+            Ok(e) => e,
+            Err(e) if e.code() == NO_SUCH_FILE => return Ok(Vec::new()),
+            Err(e) => return Err(ListError::SftpReaddirError(e)),
+        };
+        let files = entries
+            .into_iter()
+            .filter(|(_, s)| s.is_file())
+            .filter_map(|(p, s)| {
+                let name = p.file_name()?.to_string_lossy().to_string();
+                let stat = RemoteStat {
+                    size: s.size,
+                    modified: s.mtime.map(|t| UNIX_EPOCH + Duration::from_secs(t)),
+                };
+                Some((name, stat))
+            });
+        Ok(select_entries(prefix, files))
+    }
+
+    fn get(&mut self, resource: &str, output: PathBuf) -> Result<Gotten, GetError> {
+        let source = self.url(resource)?;
+        let path = self.path(resource);
+        let mut input = match self.sftp()?.open(path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("error retrieving resource {resource}: {:?}", e);
+                return Err(GetError::RetrieveSftpError(e));
+            }
+        };
+        let remote = match input.stat() {
+            Ok(s) => RemoteStat {
+                size: s.size,
+                modified: s.mtime.map(|t| UNIX_EPOCH + Duration::from_secs(t)),
+            },
+            Err(_) => RemoteStat::default(),
+        };
+        let mut partial = self.create_output(&output)?;
+        let mut bw = BufWriter::new(&mut partial);
+        let tot = match io::copy(&mut input, &mut bw) {
+            Ok(n) => n,
+            Err(e) => return Err(GetError::SftpIOError(e)),
+        };
+        if let Err(e) = bw.flush() {
+            return Err(GetError::SftpIOError(e));
+        }
+        drop(bw);
+        debug!("read and wrote {tot} bytes for file {resource}");
+        let g = Gotten::new("application/octet-stream", resource, source, output, tot);
+        partial.finish(g.with_remote(remote))
+    }
+
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creds(url: &str, opts: RemoteOptions) -> Result<SftpCredentials, RCFactoryError> {
+        SftpCredentials::try_from((&Url::parse(url).unwrap(), &opts))
+    }
+
+    #[test]
+    fn credentials() {
+        let c = creds("sftp://alice@host/dir", RemoteOptions::new()).unwrap();
+        assert_eq!("alice", c.user);
+        assert!(matches!(c.auth, SftpAuth::Agent));
+        assert!(c.known_hosts.ends_with(".ssh/known_hosts"));
+        assert!(!c.insecure_skip_host_key, "host keys checked by default");
+
+        let o = RemoteOptions::new()
+            .with("known_hosts", "/etc/ssh/ssh_known_hosts")
+            .with("insecure_skip_host_key", "yes");
+        assert!(matches!(
+            creds("sftp://alice@host/dir", o),
+            Err(RCFactoryError::InvalidOption(..))
+        ));
+
+        let o = RemoteOptions::new()
+            .with("user", "bob")
+            .with("key", "/keys/id_ed25519");
+        let c = creds("sftp://host/dir", o).unwrap();
+        assert_eq!("bob", c.user);
+        assert!(matches!(c.auth, SftpAuth::Key(_, None)));
+
+        let o = RemoteOptions::new()
+            .with("user", "bob")
+            .with("password", "hunter2");
+        let c = creds("sftp://host/dir", o).unwrap();
+        assert!(!format!("{c:?}").contains("hunter2"));
+
+        assert!(matches!(
+            creds("sftp://host/dir", RemoteOptions::new()),
+            Err(RCFactoryError::MissingOption(_))
+        ));
+        let o = RemoteOptions::new()
+            .with("user", "bob")
+            .with("key", "/keys/id_ed25519")
+            .with("password", "hunter2");
+        assert!(matches!(
+            creds("sftp://host/dir", o),
+            Err(RCFactoryError::InvalidOption(..))
+        ));
+    }
+}
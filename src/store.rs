@@ -1,9 +1,13 @@
 //! Local data storage.
 
 pub mod file;
-pub use file::FileStore;
+pub use file::{FileStore, REPLACED};
 pub mod error;
 pub use error::{StoreError, StoreGetError};
 
 pub mod file_list;
 pub use file_list::FileList;
+pub mod meta;
+pub use meta::{CaptureMeta, MetaError};
+pub mod validity;
+pub use validity::{Invalidity, Validity, QUARANTINE};
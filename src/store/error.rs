@@ -15,4 +15,6 @@ pub enum StoreGetError {
     NotAFile(PathBuf),
     NoSuchFile(PathBuf),
     IncomprehensibleFilename(OsString),
+    Quarantined(PathBuf), // where an invalid capture was moved
+    Invalid(PathBuf),     // and couldn't be moved
 }
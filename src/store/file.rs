@@ -1,24 +1,30 @@
 // a class representing a file store on local disk, geared towards
 // storing and retreiving captures and dealing in CaptureLists
 
-use super::StoreGetError;
 use super::StoreGetError::*;
+use super::{CaptureMeta, Invalidity, StoreGetError, Validity, QUARANTINE};
+use crate::remote::Gotten;
 use crate::store::StoreError::*;
 use crate::{
     Capture, CaptureList, CaptureMissing, FileList, PathMaker, PathMakerError, StoreError,
 };
-use log::{debug, info};
+use chrono::Utc;
+use log::{debug, info, warn};
 use std::ffi::OsStr;
-use std::path::{self, PathBuf};
+use std::path::{self, Path, PathBuf};
 use std::time::SystemTime;
 use std::{fmt, fs, io};
 use url::Url;
 
+/// the subdirectory of a store in which replaced captures are kept
+pub const REPLACED: &str = "replaced";
+
 pub struct FileStore {
     pub path: path::PathBuf,
     pub pathmaker: Box<dyn PathMaker>,
     // remote URL if any
     pub url: Option<Url>,
+    pub validity: Validity,
 }
 
 impl FileStore {
@@ -28,6 +34,7 @@ impl FileStore {
             path,
             pathmaker,
             url: None,
+            validity: Validity::default(),
         };
         match fs.validate() {
             Ok(_) => Ok(fs),
@@ -35,6 +42,11 @@ impl FileStore {
         }
     }
 
+    pub fn with_validity(mut self, validity: Validity) -> Self {
+        self.validity = validity;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), StoreError> {
         let localmd = fs::metadata(&self.path);
         if let Err(e) = localmd {
@@ -67,13 +79,107 @@ impl FileStore {
         }
         match fetched.file_name() {
             Some(f) => match self.filename_to_systime(f) {
-                Ok(time) => Ok(Capture::from((fetched, time))),
+                Ok(time) => match self.validity.check(&fetched) {
+                    Ok(_) => Ok(self.capture(fetched, time)),
+                    Err(why) => Err(self.quarantine(&fetched, why)),
+                },
                 Err(_) => Err(IncomprehensibleFilename(f.to_os_string())),
             },
             None => Err(NotAFile(fetched)), // FIXME: not sure about this state
         }
     }
 
+    // a capture, with its provenance where that was recorded
+    fn capture(&self, path: PathBuf, time: SystemTime) -> Capture {
+        let meta = CaptureMeta::read(&path);
+        let c = Capture::from((path, time));
+        match meta {
+            Ok(Some(m)) => c.with_meta(m),
+            Ok(None) => c,
+            Err(e) => {
+                warn!("ignoring the record of {}: {:?}", c.path.display(), e);
+                c
+            }
+        }
+    }
+
+    /// record the provenance of a download, beside it
+    pub fn record(&self, g: &Gotten) -> Option<CaptureMeta> {
+        let written = CaptureMeta::from_gotten(g).and_then(|m| m.write(&g.output).map(|_| m));
+        match written {
+            Ok(m) => Some(m),
+            Err(e) => {
+                warn!("can't record {}: {:?}", g.output.display(), e);
+                None
+            }
+        }
+    }
+
+    /// put a fresh download of a capture in its place, discarding the
+    /// old one or keeping it under "replaced/", by when it was fetched
+    pub fn replace(&self, old: &Capture, mut g: Gotten, keep: bool) -> io::Result<Capture> {
+        if keep {
+            let fetched = match &old.meta {
+                Some(m) => m.fetched,
+                None => Utc::now(),
+            };
+            let kept = self
+                .path
+                .join(REPLACED)
+                .join(fetched.format("%Y%m%dT%H%M%SZ").to_string())
+                .join(self.relative(&old.path));
+            if let Some(d) = kept.parent() {
+                fs::create_dir_all(d)?;
+            }
+            fs::rename(&old.path, &kept)?;
+            let sidecar = CaptureMeta::sidecar(&old.path);
+            if sidecar.exists() {
+                fs::rename(&sidecar, CaptureMeta::sidecar(&kept))?;
+            }
+            info!("kept replaced {} as {}", old.path.display(), kept.display());
+        }
+        fs::rename(&g.output, &old.path)?;
+        g.output = old.path.clone();
+        let c = Capture::from((old.path.clone(), old.time));
+        Ok(match self.record(&g) {
+            Some(m) => c.with_meta(m),
+            None => c,
+        })
+    }
+
+    // where a file is within the store
+    fn relative<'a>(&self, p: &'a Path) -> &'a Path {
+        match p.strip_prefix(&self.path) {
+            Ok(r) => r,
+            Err(_) => Path::new(p.file_name().unwrap_or_default()),
+        }
+    }
+
+    // move an invalid capture aside, where the next fill won't find it
+    fn quarantine(&self, fetched: &Path, why: Invalidity) -> StoreGetError {
+        let q = self.path.join(QUARANTINE).join(self.relative(fetched));
+        warn!("quarantining {}: {:?}", fetched.display(), why);
+        let moved = match q.parent() {
+            Some(d) => fs::create_dir_all(d),
+            None => Ok(()),
+        };
+        match moved.and_then(|_| fs::rename(fetched, &q)) {
+            Ok(_) => {
+                let sidecar = CaptureMeta::sidecar(fetched);
+                if sidecar.exists() {
+                    if let Err(e) = fs::rename(&sidecar, CaptureMeta::sidecar(&q)) {
+                        warn!("can't quarantine {}: {e}", sidecar.display());
+                    }
+                }
+                Quarantined(q)
+            }
+            Err(e) => {
+                warn!("can't quarantine {}: {e}", fetched.display());
+                Invalid(fetched.to_path_buf())
+            }
+        }
+    }
+
     pub fn get_str(&self, p: &str) -> Result<Capture, StoreGetError> {
         self.get(&PathBuf::from(p))
     }
@@ -105,6 +211,7 @@ impl FileStore {
                             cs,
                             self.path.to_str().unwrap()
                         ),
+                        Quarantined(_) | Invalid(_) => (), // already logged
                         _ => eprintln!("error on getting capture '{}': {:?}", cs, e),
                     };
                 }
@@ -113,14 +220,30 @@ impl FileStore {
         cl
     }
 
+    // every file in the store, dated by our pathmaker where it can,
+    // otherwise by its modification time
     pub fn all_captures(&self) -> Result<CaptureList, StoreGetError> {
         let mut ll = CaptureList::empty();
         for ent in fs::read_dir(&self.path).unwrap() {
             match ent {
                 Ok(ent) => {
                     let p = ent.path();
-                    if p.is_file() {
-                        ll.push(Capture::from(p));
+                    // nor sidecars and partial downloads, which are hidden
+                    if !p.is_file() || ent.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    let time = match self.filename_to_systime(&ent.file_name()) {
+                        Ok(time) => time,
+                        Err(e) => {
+                            debug!("dating {} by its modification time: {:?}", p.display(), e);
+                            Capture::from(p.clone()).time
+                        }
+                    };
+                    match self.validity.check(&p) {
+                        Ok(_) => ll.push(self.capture(p, time)),
+                        Err(why) => {
+                            self.quarantine(&p, why);
+                        }
                     }
                 }
                 Err(e) => eprintln!("error on {}: {}", self.path.display(), e),
@@ -141,6 +264,7 @@ impl From<FileStoreConfig> for FileStore {
             path: PathBuf::from(cfg.path),
             pathmaker: cfg.pathmaker,
             url: None,
+            validity: Validity::default(),
         }
     }
 }
@@ -155,9 +279,10 @@ impl fmt::Display for FileStore {
 mod tests {
     use super::*;
     use crate::pathmaker;
-    use chrono::Utc;
+    use chrono::{TimeZone, Utc};
     use std::env;
     use std::ffi::OsString;
+    use std::fs::File;
 
     static MOCK_FILE: &str = "2023-11-21T14:40:00+00:00";
 
@@ -180,6 +305,7 @@ mod tests {
             path: pbuf,
             pathmaker,
             url: None,
+            validity: Validity::default(),
         }
     }
 
@@ -214,6 +340,125 @@ mod tests {
         assert!(c.valid());
     }
 
+    #[test]
+    fn pattern_store() {
+        let mut pbuf = env::temp_dir();
+        pbuf.push("reflector_pattern_store_test");
+        fs::create_dir_all(&pbuf).unwrap();
+        for (f, data) in [
+            ("20231013_1024_0094.ogv", "OggS just testing"),
+            (
+                "20231014_588_SDO_VO2.mp4",
+                "\0\0\0\x18ftypmp42 just testing",
+            ),
+            ("notes.txt", "just testing"),
+        ] {
+            fs::write(pbuf.join(f), data).unwrap();
+        }
+        let pathmaker = Box::new(
+            pathmaker::Pattern::new(
+                r"^(?<year>\d{4})(?<month>\d\d)(?<day>\d\d)_.+\.(ogv|mp4)$",
+                "%Y%m%d_1024_0094.ogv",
+            )
+            .unwrap(),
+        );
+        let m = FileStore {
+            path: pbuf,
+            pathmaker,
+            url: None,
+            validity: Validity::default(),
+        };
+
+        let notes = Utc.with_ymd_and_hms(2023, 10, 1, 12, 0, 0).unwrap();
+        File::options()
+            .write(true)
+            .open(m.path.join("notes.txt"))
+            .unwrap()
+            .set_modified(SystemTime::from(notes))
+            .unwrap();
+
+        let all = m.all_captures().unwrap();
+        assert_eq!(3, all.len(), "every file");
+        let latest = all.latest().unwrap();
+        assert_eq!("20231014_588_SDO_VO2.mp4", latest.path.file_name().unwrap());
+        let expect = Utc.with_ymd_and_hms(2023, 10, 14, 0, 0, 0).unwrap();
+        assert_eq!(SystemTime::from(expect), latest.time);
+        let earliest = &all.list[0];
+        assert_eq!("notes.txt", earliest.path.file_name().unwrap());
+        assert_eq!(SystemTime::from(notes), earliest.time, "by mtime");
+
+        let l = FileList::from(vec![
+            "20231014_588_SDO_VO2.mp4".to_string(),
+            "20231015_1024_0094.ogv".to_string(),
+        ]);
+        let c = m.captures_in_list(l);
+        assert_eq!(1, c.list.len(), "found");
+        assert_eq!(1, c.missing.len(), "missing");
+        let expect = Utc.with_ymd_and_hms(2023, 10, 15, 0, 0, 0).unwrap();
+        assert_eq!(SystemTime::from(expect), c.missing[0].time);
+    }
+
+    #[test]
+    fn hidden() {
+        let pbuf = env::temp_dir().join("reflector_hidden_store_test");
+        let _ = fs::remove_dir_all(&pbuf);
+        fs::create_dir_all(&pbuf).unwrap();
+        let mp4 = "\0\0\0\x18ftypmp42 just testing";
+        fs::write(pbuf.join("20231013_588_SDO_VO2.mp4"), mp4).unwrap();
+        fs::write(pbuf.join(".20231013_588_SDO_VO2.mp4.meta"), "size = 1").unwrap();
+        fs::write(pbuf.join(".20231014_588_SDO_VO2.mp4.partial"), "\0\0").unwrap();
+        // unanchored, so it would date the hidden files too
+        let pathmaker = Box::new(
+            pathmaker::Pattern::new(
+                r"(?<year>\d{4})(?<month>\d\d)(?<day>\d\d)_.+\.mp4",
+                "%Y%m%d_1024_0094.mp4",
+            )
+            .unwrap(),
+        );
+        let m = FileStore::new(&pbuf.display().to_string(), pathmaker).unwrap();
+        let all = m.all_captures().unwrap();
+        assert_eq!(1, all.len(), "only the capture");
+        assert_eq!(
+            "20231013_588_SDO_VO2.mp4",
+            all.list[0].path.file_name().unwrap()
+        );
+    }
+
+    #[test]
+    fn quarantine() {
+        let pbuf = env::temp_dir().join("reflector_quarantine_store_test");
+        let _ = fs::remove_dir_all(&pbuf);
+        fs::create_dir_all(&pbuf).unwrap();
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        fs::write(pbuf.join("20231013.png"), png).unwrap();
+        fs::write(pbuf.join("20231014.png"), "").unwrap();
+        fs::write(pbuf.join("20231015.png"), "<html>oops</html>").unwrap();
+        let pathmaker = Box::new(pathmaker::Template::new("%Y%m%d.png").unwrap());
+        let m = FileStore::new(&pbuf.display().to_string(), pathmaker).unwrap();
+
+        let l = FileList::from(vec!["20231013.png".to_string(), "20231014.png".to_string()]);
+        let c = m.captures_in_list(l);
+        assert_eq!(1, c.list.len(), "found");
+        assert_eq!(1, c.missing.len(), "zero length");
+        let q = pbuf.join(QUARANTINE);
+        assert!(q.join("20231014.png").is_file());
+        assert!(!pbuf.join("20231014.png").exists());
+
+        let all = m.all_captures().unwrap();
+        assert_eq!(1, all.len(), "not the HTML");
+        assert!(q.join("20231015.png").is_file());
+
+        // and with decoding, not even the PNG which is only a header
+        let m = m.with_validity(Validity {
+            decode: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            Err(Quarantined(q.join("20231013.png"))),
+            m.get_str("20231013.png")
+        );
+    }
+
     #[test]
     fn captures_in_list() {
         let m = mock_file_store();
@@ -0,0 +1,160 @@
+// the provenance of a capture, kept in a small TOML sidecar beside it,
+// hidden as ".NAME.meta", as the store passes over hidden files when
+// looking for captures
+
+use crate::remote::{redacted, Fetched, Gotten};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureMeta {
+    pub url: Url,
+    pub mimetype: String,
+    pub size: u64,
+    pub sha256: String, // in hex
+    pub fetched: DateTime<Utc>,
+    pub remote_modified: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum MetaError {
+    IOError(io::Error),
+    Unparsable(toml::de::Error),
+    Unwritable(toml::ser::Error),
+}
+use MetaError::*;
+
+impl CaptureMeta {
+    /// the record of a download just made, hashing its output
+    pub fn from_gotten(g: &Gotten) -> Result<CaptureMeta, MetaError> {
+        let sha256 = match sha256_file(&g.output) {
+            Ok(h) => h,
+            Err(e) => return Err(IOError(e)),
+        };
+        // kept without any password the remote URL had
+        let url = Url::parse(&redacted(g.source.as_str())).unwrap_or(g.source.clone());
+        Ok(CaptureMeta {
+            url,
+            mimetype: g.mimetype.clone(),
+            size: g.size,
+            sha256,
+            fetched: Utc::now(),
+            remote_modified: g.remote_modified.map(DateTime::from),
+            etag: g.etag.clone(),
+        })
+    }
+
+    /// what was known of the file upstream, to tell if it's changed
+    pub fn fetched(&self) -> Fetched {
+        Fetched {
+            size: Some(self.size),
+            modified: self.remote_modified.map(SystemTime::from),
+            etag: self.etag.clone(),
+        }
+    }
+
+    /// where the record for a capture is kept
+    pub fn sidecar(capture: &Path) -> PathBuf {
+        let name = capture.file_name().unwrap_or_default().to_string_lossy();
+        capture.with_file_name(format!(".{name}.meta"))
+    }
+
+    /// the record for a capture, if it has one
+    pub fn read(capture: &Path) -> Result<Option<CaptureMeta>, MetaError> {
+        let s = match fs::read_to_string(CaptureMeta::sidecar(capture)) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(IOError(e)),
+        };
+        match toml::from_str(&s) {
+            Ok(m) => Ok(Some(m)),
+            Err(e) => Err(Unparsable(e)),
+        }
+    }
+
+    pub fn write(&self, capture: &Path) -> Result<(), MetaError> {
+        let s = match toml::to_string(self) {
+            Ok(s) => s,
+            Err(e) => return Err(Unwritable(e)),
+        };
+        match fs::write(CaptureMeta::sidecar(capture), s) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(IOError(e)),
+        }
+    }
+}
+
+/// the SHA-256 of a file's contents, in hex
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut file = File::open(path)?;
+    let mut buf = [0; 8192];
+    loop {
+        match file.read(&mut buf)? {
+            0 => break,
+            n => hasher.update(&buf[..n]),
+        }
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::Duration;
+
+    #[test]
+    fn round_trip() {
+        let d = env::temp_dir().join("reflector-meta-test");
+        fs::create_dir_all(&d).unwrap();
+        let capture = d.join("20231014_0700.txt");
+        fs::write(&capture, "abc").unwrap();
+        let source = Url::parse("https://example.org/2023/10/14/20231014_0700.txt").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_697_266_800);
+        let g = Gotten::new(
+            "text/plain",
+            "20231014_0700.txt",
+            source,
+            capture.clone(),
+            3,
+        )
+        .with_remote(crate::remote::RemoteStat {
+            size: Some(3),
+            modified: Some(modified),
+        });
+
+        let m = CaptureMeta::from_gotten(&g).unwrap();
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            m.sha256
+        );
+        assert_eq!(Some(DateTime::from(modified)), m.remote_modified);
+        assert_eq!(Some(modified), m.fetched().modified);
+        m.write(&capture).unwrap();
+        assert_eq!(
+            d.join(".20231014_0700.txt.meta"),
+            CaptureMeta::sidecar(&capture)
+        );
+        assert_eq!(Some(m), CaptureMeta::read(&capture).unwrap());
+
+        assert_eq!(None, CaptureMeta::read(&d.join("none.txt")).unwrap());
+        fs::write(CaptureMeta::sidecar(&capture), "not = [toml").unwrap();
+        assert!(matches!(
+            CaptureMeta::read(&capture),
+            Err(MetaError::Unparsable(_))
+        ));
+    }
+}
@@ -0,0 +1,120 @@
+// what makes a file in the store a capture, beyond a name the
+// pathmaker can date: a minimum size, the magic bytes of the media
+// type its name suggests, and optionally its whole structure
+
+use crate::MediaType;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// the subdirectory of a store to which invalid captures are moved
+pub const QUARANTINE: &str = "quarantine";
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Validity {
+    /// the fewest bytes a capture may have
+    pub min_size: u64,
+    /// whether a capture named as a PNG, JPEG, Ogg or MP4 file must
+    /// start with that format's magic bytes
+    pub magic: bool,
+    /// whether such captures are read in full, to check they're
+    /// complete and uncorrupted
+    pub decode: bool,
+}
+
+impl Default for Validity {
+    fn default() -> Self {
+        Validity {
+            min_size: 1,
+            magic: true,
+            decode: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Invalidity {
+    Unreadable(io::Error),
+    TooSmall(u64, u64),
+    NotOfMediaType(MediaType),
+    Undecodable(MediaType, String),
+}
+use Invalidity::*;
+
+impl Validity {
+    pub fn check(&self, path: &Path) -> Result<(), Invalidity> {
+        let size = match fs::metadata(path) {
+            Ok(m) => m.len(),
+            Err(e) => return Err(Unreadable(e)),
+        };
+        if size < self.min_size {
+            return Err(TooSmall(size, self.min_size));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let media = match MediaType::from_name(&name) {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        if self.magic {
+            match MediaType::sniff_file(path) {
+                Ok(Some(m)) if m == media => (),
+                Ok(_) => return Err(NotOfMediaType(media)),
+                Err(e) => return Err(Unreadable(e)),
+            }
+        }
+        if self.decode {
+            let file = match File::open(path) {
+                Ok(f) => f,
+                Err(e) => return Err(Unreadable(e)),
+            };
+            if let Err(why) = media.decode(BufReader::new(file)) {
+                return Err(Undecodable(media, why));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn check() {
+        let d = env::temp_dir().join("reflector-validity-test");
+        fs::create_dir_all(&d).unwrap();
+        let file = |name: &str, data: &[u8]| {
+            let p = d.join(name);
+            fs::write(&p, data).unwrap();
+            p
+        };
+        let v = Validity::default();
+        assert!(matches!(v.check(&file("a.txt", b"")), Err(TooSmall(0, 1))));
+        v.check(&file("b.txt", b"notes")).unwrap();
+        let html = file("c.png", b"<html>oops</html>");
+        assert!(matches!(
+            v.check(&html),
+            Err(NotOfMediaType(MediaType::Png))
+        ));
+        let png = file("d.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        v.check(&png).unwrap();
+        let decode = Validity {
+            decode: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            decode.check(&png),
+            Err(Undecodable(MediaType::Png, _))
+        ));
+        let lax = Validity {
+            min_size: 0,
+            magic: false,
+            decode: false,
+        };
+        lax.check(&html).unwrap();
+        assert!(matches!(v.check(&d.join("none")), Err(Unreadable(_))));
+    }
+}
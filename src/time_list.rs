@@ -22,7 +22,7 @@
 #![allow(unused_imports)]
 use super::time_util::*;
 use super::{CaptureList, TimeRange};
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use std::collections::VecDeque;
 use std::fmt;
 use std::time::{Duration, SystemTime};
@@ -86,35 +86,88 @@ impl From<DateTime<Utc>> for TimeList {
     }
 }
 
-/// Convert from a range, a period and an offset.
+/// Convert from a range, a period and an offset, with the offset
+/// anchored to midnight UTC.
 impl From<(TimeRange, Duration, Duration)> for TimeList {
     fn from(input: (TimeRange, Duration, Duration)) -> Self {
+        Self::from((input.0, input.1, input.2, Utc))
+    }
+}
+
+/// Convert from a range, a period, an offset and the timezone in which
+/// the offset is anchored to midnight.
+///
+/// Times are stepped by period on the local wall clock, so a daily
+/// period stays at the same local time of day across DST transitions.
+/// Local times skipped by DST are dropped, and repeated local times
+/// are taken once, at the earlier instant.
+///
+/// # Example
+/// ```
+/// use chrono::{TimeZone, Timelike, Utc};
+/// use chrono_tz::America::New_York;
+/// use reflector::{TimeList, TimeRange};
+/// use std::time::Duration;
+///
+/// // spanning the spring forward DST transition
+/// let from = Utc.with_ymd_and_hms(2024, 3, 8, 0, 0, 0).unwrap();
+/// let to = Utc.with_ymd_and_hms(2024, 3, 13, 0, 0, 0).unwrap();
+/// let day = Duration::from_secs(24 * 60 * 60);
+/// let offset = Duration::from_secs(21 * 60 * 60 + 5 * 60);
+/// let tl = TimeList::from((TimeRange::from((from, to)), day, offset, New_York));
+/// assert_eq!(5, tl.len());
+/// for t in tl {
+///     let local = chrono::DateTime::<Utc>::from(t).with_timezone(&New_York);
+///     assert_eq!((21, 5), (local.hour(), local.minute()));
+/// }
+/// ```
+impl<Tz: TimeZone> From<(TimeRange, Duration, Duration, Tz)> for TimeList {
+    fn from(input: (TimeRange, Duration, Duration, Tz)) -> Self {
         let range = input.0;
-        let period = input.1;
-        let offset = input.2;
+        let tz = input.3;
+        let zero = chrono::Duration::zero();
+        let (period, offset) = match (
+            chrono::Duration::from_std(input.1),
+            chrono::Duration::from_std(input.2),
+        ) {
+            (Ok(p), Ok(o)) if p > zero => (p, o),
+            // no times in a zero period, or one beyond the calendar
+            _ => return Self::empty(),
+        };
 
-        // start is the leading edge of the range, minus the offset (for now)
-        let from = datetime_from_systime(range.from) - offset;
+        // start is the leading edge of the range on the local clock,
+        // minus the offset (for now)
+        let from = datetime_from_systime(range.from)
+            .with_timezone(&tz)
+            .naive_local()
+            .checked_sub_signed(offset);
+        let from = match from {
+            Some(f) => f,
+            None => return Self::empty(),
+        };
 
         // make a duration between start and midnight
-        let since_midnight = datetime_since_midnight(&from);
+        let midnight = from.date().and_hms_opt(0, 0, 0).unwrap();
+        let since_midnight = from - midnight;
 
         // divide that by period to get x
-        let x = since_midnight / (period.as_secs() as u32);
+        let x = since_midnight.num_seconds() / period.num_seconds();
 
         // our initial time is (x * period) + offset
-        let start = datetime_trunc_midnight(&from) + (period * x.as_secs() as u32) + offset;
-
-        // shift back from Chrono to std
-        let start = systime_from_datetime(start);
+        let mut tt = midnight + (period * x as i32) + offset;
 
         // stack on one per period, accumulating the period
         // until our accum is larger than our end time
         let mut l = Self::empty();
-        let mut tt = start;
-        while tt <= range.to {
-            if range.contains(tt) {
-                l.push(tt);
+        loop {
+            if let Some(t) = local_to_utc(&tt, &tz) {
+                let t = systime_from_datetime(t);
+                if t > range.to {
+                    break;
+                }
+                if range.contains(t) {
+                    l.push(t);
+                }
             }
             tt += period;
         }
@@ -216,6 +269,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_zoned_range() {
+        use chrono::{TimeZone, Timelike};
+        use chrono_tz::America::New_York;
+
+        // spanning fall back, when 01:30 happens twice
+        let from = Utc.with_ymd_and_hms(2024, 11, 2, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 11, 5, 0, 0, 0).unwrap();
+        let range = TimeRange::from((from, to));
+        let day = Duration::from_secs(24 * 60 * 60);
+        let offset = Duration::from_secs(90 * 60);
+        let l = TimeList::from((range.clone(), day, offset, New_York));
+        assert_eq!(3, l.len());
+        for t in l {
+            let local = DateTime::<Utc>::from(t).with_timezone(&New_York);
+            assert_eq!((1, 30), (local.hour(), local.minute()), "{local}");
+        }
+
+        // spanning spring forward, when 02:30 never happens
+        let from = Utc.with_ymd_and_hms(2024, 3, 9, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 3, 12, 0, 0, 0).unwrap();
+        let range = TimeRange::from((from, to));
+        let offset = Duration::from_secs(150 * 60);
+        let l = TimeList::from((range, day, offset, New_York));
+        assert_eq!(2, l.len(), "{l}");
+
+        // UTC matches the zoneless conversion
+        let offset = Duration::from_secs(75 * 60);
+        assert_eq!(
+            TimeList::from((range_of_days(3), day, offset)),
+            TimeList::from((range_of_days(3), day, offset, Utc))
+        );
+    }
+
+    #[test]
+    fn from_degenerate() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let l = TimeList::from((range_of_days(1), Duration::ZERO, Duration::ZERO));
+        assert!(l.is_empty(), "zero period");
+        let l = TimeList::from((range_of_days(1), day, Duration::MAX));
+        assert!(l.is_empty(), "offset beyond the calendar");
+        let l = TimeList::from((range_of_days(1), Duration::MAX, Duration::ZERO));
+        assert!(l.is_empty(), "period beyond the calendar");
+    }
+
+    fn range_of_days(days: u64) -> TimeRange {
+        let to = systime_round_to_min(&SystemTime::now());
+        TimeRange::new(to - Duration::from_secs(days * 24 * 60 * 60), to).unwrap()
+    }
+
     #[test]
     fn clone() {
         let now = SystemTime::now();
@@ -1,7 +1,7 @@
 //! Handy functions for working with the time formats used in the
 //! reflector crate.
 
-use chrono::{DateTime, Timelike, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Timelike, Utc};
 use std::time::{Duration, SystemTime};
 
 /// convert a SystemTime into seconds since midnight, or else u64::MAX
@@ -105,9 +105,6 @@ fn pluralize(u: u64) -> String {
 /// assert_eq!("1 minute, 2 seconds", display_duration(&d));
 /// ```
 pub fn display_duration(d: &Duration) -> String {
-    const MINUTE: u64 = 60;
-    const HOUR: u64 = MINUTE * 60;
-    const DAY: u64 = HOUR * 24;
     let mut s = vec!["".to_string()];
     let mut v = d.as_secs();
     if v > DAY {
@@ -130,6 +127,144 @@ pub fn display_duration(d: &Duration) -> String {
     s[1..].join(", ")
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum DurationParseError {
+    Empty,
+    MissingUnit(String),
+    UnknownUnit(char),
+    Unparsable(String),
+    OutOfRange(String),
+}
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = MINUTE * 60;
+const DAY: u64 = HOUR * 24;
+const WEEK: u64 = DAY * 7;
+
+/// parse a human-readable duration: a plain count of seconds, one or
+/// more counts with units (s, m, h, d or w), or a time of day
+/// ```
+/// use reflector::time_util::*;
+/// use std::time::Duration;
+///
+/// assert_eq!(Duration::from_secs(600), parse_duration("600").unwrap());
+/// assert_eq!(Duration::from_secs(600), parse_duration("10m").unwrap());
+/// assert_eq!(Duration::from_secs(5400), parse_duration("1h30m").unwrap());
+/// assert_eq!(Duration::from_secs(28 * 86400), parse_duration("28d").unwrap());
+/// assert_eq!(Duration::from_secs(75900), parse_duration("21:05").unwrap());
+/// assert!(parse_duration("10 parsecs").is_err());
+/// ```
+pub fn parse_duration(s: &str) -> Result<Duration, DurationParseError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(DurationParseError::Empty);
+    }
+    if s.contains(':') {
+        return parse_time_of_day(s);
+    }
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut tot: u64 = 0;
+    let mut num = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => MINUTE,
+            'h' => HOUR,
+            'd' => DAY,
+            'w' => WEEK,
+            x => return Err(DurationParseError::UnknownUnit(x)),
+        };
+        let n: u64 = match num.parse() {
+            Ok(n) => n,
+            Err(_) => return Err(DurationParseError::Unparsable(s.to_string())),
+        };
+        tot = match n.checked_mul(unit).and_then(|x| x.checked_add(tot)) {
+            Some(t) => t,
+            None => return Err(DurationParseError::OutOfRange(s.to_string())),
+        };
+        num.clear();
+    }
+    if !num.is_empty() {
+        return Err(DurationParseError::MissingUnit(s.to_string()));
+    }
+    Ok(Duration::from_secs(tot))
+}
+
+// HH:MM or HH:MM:SS, as a duration since midnight
+fn parse_time_of_day(s: &str) -> Result<Duration, DurationParseError> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(DurationParseError::Unparsable(s.to_string()));
+    }
+    let mut nums = vec![];
+    for p in parts {
+        match p.parse::<u64>() {
+            Ok(n) => nums.push(n),
+            Err(_) => return Err(DurationParseError::Unparsable(s.to_string())),
+        }
+    }
+    let secs = nums.get(2).copied().unwrap_or(0);
+    if nums[0] > 23 || nums[1] > 59 || secs > 59 {
+        return Err(DurationParseError::OutOfRange(s.to_string()));
+    }
+    Ok(Duration::from_secs(
+        nums[0] * HOUR + nums[1] * MINUTE + secs,
+    ))
+}
+
+/// show a duration in the compact form accepted by [parse_duration]
+/// ```
+/// use reflector::time_util::*;
+/// use std::time::Duration;
+///
+/// assert_eq!("10m", display_compact_duration(&Duration::from_secs(600)));
+/// assert_eq!("1h30m", display_compact_duration(&Duration::from_secs(5400)));
+/// assert_eq!("28d", display_compact_duration(&Duration::from_secs(28 * 86400)));
+/// assert_eq!("0s", display_compact_duration(&Duration::ZERO));
+/// ```
+pub fn display_compact_duration(d: &Duration) -> String {
+    let mut v = d.as_secs();
+    if v == 0 {
+        return "0s".to_string();
+    }
+    let mut s = String::new();
+    for (unit, suffix) in [(DAY, 'd'), (HOUR, 'h'), (MINUTE, 'm'), (1, 's')] {
+        if v >= unit {
+            s.push_str(&format!("{}{suffix}", v / unit));
+            v %= unit;
+        }
+    }
+    s
+}
+
+/// show a duration since midnight as a time of day, falling back to
+/// the compact form for a day or longer
+/// ```
+/// use reflector::time_util::*;
+/// use std::time::Duration;
+///
+/// assert_eq!("21:05", display_time_of_day(&Duration::from_secs(75900)));
+/// assert_eq!("00:00:30", display_time_of_day(&Duration::from_secs(30)));
+/// assert_eq!("1d", display_time_of_day(&Duration::from_secs(86400)));
+/// ```
+pub fn display_time_of_day(d: &Duration) -> String {
+    let v = d.as_secs();
+    if v >= DAY {
+        return display_compact_duration(d);
+    }
+    match v % MINUTE {
+        0 => format!("{:02}:{:02}", v / HOUR, (v % HOUR) / MINUTE),
+        s => format!("{:02}:{:02}:{:02}", v / HOUR, (v % HOUR) / MINUTE, s),
+    }
+}
+
 /// how long since midnight happened?
 /// ```
 /// use reflector::time_util::*;
@@ -156,6 +291,14 @@ pub fn datetime_trunc_midnight(inb: &DateTime<Utc>) -> DateTime<Utc> {
     *inb - datetime_since_midnight(inb)
 }
 
+/// convert a local wall clock time into UTC, picking the earlier time
+/// when ambiguous, or None where the local time doesn't exist
+pub fn local_to_utc<Tz: TimeZone>(local: &NaiveDateTime, tz: &Tz) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(local)
+        .earliest()
+        .map(|d| d.with_timezone(&Utc))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,4 +325,30 @@ mod tests {
             "expected vs actual current time"
         );
     }
+
+    #[test]
+    fn duration_round_trip() {
+        for s in ["10m", "23h", "28d", "1d2h3m4s", "59s"] {
+            let d = parse_duration(s).unwrap();
+            assert_eq!(s, display_compact_duration(&d));
+        }
+        assert_eq!(Duration::from_secs(2 * WEEK), parse_duration("2w").unwrap());
+    }
+
+    #[test]
+    fn duration_errors() {
+        use DurationParseError::*;
+        assert_eq!(Err(Empty), parse_duration(" "));
+        assert_eq!(Err(UnknownUnit('y')), parse_duration("1y"));
+        assert_eq!(Err(MissingUnit("1h30".to_string())), parse_duration("1h30"));
+        assert_eq!(Err(Unparsable("h".to_string())), parse_duration("h"));
+        assert_eq!(
+            Err(OutOfRange("25:00".to_string())),
+            parse_duration("25:00")
+        );
+        assert_eq!(
+            Err(Unparsable("1:2:3:4".to_string())),
+            parse_duration("1:2:3:4")
+        );
+    }
 }
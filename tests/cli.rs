@@ -0,0 +1,33 @@
+use assert_cmd::Command;
+
+fn this() -> Command {
+    Command::cargo_bin("reflector").unwrap()
+}
+
+#[test]
+fn help() {
+    this().arg("--help").assert().success();
+}
+
+#[test]
+fn unknown_subcommand() {
+    this().arg("frobnicate").assert().failure();
+}
+
+#[test]
+fn sources() {
+    let out = this()
+        .env("XDG_CONFIG_HOME", "/nonexistent")
+        .env_remove("REFLECTOR_CONFIG")
+        .arg("sources")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&out.get_output().stdout).to_string();
+    assert!(stdout.contains("sdo:"), "built-in sources listed");
+}
+
+#[test]
+#[ignore] // since the default mode requires local storage, which is not yet accompocated for...
+fn status() {
+    this().arg("status").assert().success().stderr("");
+}
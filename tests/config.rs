@@ -1,9 +1,11 @@
 use reflector::Config;
+use std::path::PathBuf;
 
 fn basic_toml() -> &'static str {
     r#"
 verbose = false
 loops = 1
+storage_root = "/srv/sat"
 [[sources]]
 name      = "Solar Data Observatory"
 abbrev    = "sdo"
@@ -25,5 +27,13 @@ period    = 600
 
 #[test]
 fn test_basic_toml() {
-    let _basic: Config = toml::from_str(basic_toml()).unwrap();
+    let basic: Config = toml::from_str(basic_toml()).unwrap();
+    assert_eq!(Some(PathBuf::from("/srv/sat")), basic.storage_root);
+}
+
+#[test]
+fn test_sourceless_toml() {
+    let c: Config = toml::from_str("verbose = true").unwrap();
+    assert!(c.verbose);
+    assert!(!c.sources.is_empty(), "falls back to built-in sources");
 }
@@ -0,0 +1,426 @@
+// the FTP client against a small in-process FTP server on the loopback
+// interface, which serves a temporary directory read-only
+
+use chrono::{DateTime, Utc};
+use reflector::remote::{self, Ftp, FtpCredentials, FtpOptions, RemoteOptions};
+use reflector::remote::{ConnectError, PingError};
+use reflector::{GetError, RemoteClient};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::SystemTime;
+use url::Url;
+
+const USER: &str = "reflector";
+const PASSWORD: &str = "s3cret";
+const DATA: &str = "upstream data\n";
+
+#[derive(Clone, Copy)]
+struct Behaviour {
+    mlst: bool,      // else answer 500, as older servers do
+    hang_ups: usize, // times to drop the connection on RETR
+    cut_offs: usize, // times to drop it halfway through a RETR
+}
+
+const MLST: Behaviour = Behaviour {
+    mlst: true,
+    hang_ups: 0,
+    cut_offs: 0,
+};
+
+// transfers resumed with REST, across all servers
+static RESUMED: AtomicUsize = AtomicUsize::new(0);
+
+struct Session {
+    root: PathBuf,
+    cwd: String,
+    behaviour: Behaviour,
+    passive: Option<TcpListener>, // for the next transfer
+    hang_ups: Arc<AtomicUsize>,   // left, across all sessions
+    cut_offs: Arc<AtomicUsize>,   // likewise
+    rest: usize,                  // where the next RETR starts
+}
+
+impl Session {
+    fn local(&self, path: &str) -> PathBuf {
+        let path = match path.starts_with('/') {
+            true => path.to_string(),
+            false => format!("{}/{}", self.cwd.trim_end_matches('/'), path),
+        };
+        self.root.join(path.trim_start_matches('/'))
+    }
+
+    // send the data over the passive connection, giving the replies
+    // which bracket it
+    fn transfer(&mut self, data: String) -> String {
+        let listener = match self.passive.take() {
+            Some(l) => l,
+            None => return "425 use PASV first".to_string(),
+        };
+        let (mut conn, _) = listener.accept().unwrap();
+        conn.write_all(data.as_bytes()).unwrap();
+        "150 here it comes\r\n226 done".to_string()
+    }
+
+    // the lines of a directory listing, files and directories alike
+    fn listing(&self, arg: &str, line: impl Fn(&str, &fs::Metadata) -> String) -> String {
+        let mut entries: Vec<_> = fs::read_dir(self.local(arg))
+            .unwrap()
+            .flatten()
+            .map(|e| {
+                (
+                    e.file_name().to_string_lossy().to_string(),
+                    e.metadata().unwrap(),
+                )
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.iter().map(|(n, m)| line(n, m) + "\r\n").collect()
+    }
+
+    // whether to drop the connection rather than answer
+    fn hang_up(&self, cmd: &str) -> bool {
+        cmd == "RETR" && take_one(&self.hang_ups)
+    }
+
+    // whether to send half of a file, and then drop the connection
+    fn cut_off(&mut self, cmd: &str, arg: &str, w: &mut TcpStream) -> bool {
+        if cmd != "RETR" || !self.local(arg).is_file() || !take_one(&self.cut_offs) {
+            return false;
+        }
+        let data = fs::read(self.local(arg)).unwrap();
+        let data = &data[std::mem::take(&mut self.rest)..];
+        if let Some(l) = self.passive.take() {
+            let (mut conn, _) = l.accept().unwrap();
+            let _ = w.write_all(b"150 here it comes\r\n");
+            let _ = conn.write_all(&data[..data.len() / 2]);
+        }
+        true
+    }
+
+    fn reply(&mut self, cmd: &str, arg: &str) -> String {
+        match cmd {
+            "USER" => "331 password please".to_string(),
+            "PASS" if arg == PASSWORD => "230 logged in".to_string(),
+            "PASS" => "530 login incorrect".to_string(),
+            "NOOP" => "200 ok".to_string(),
+            "TYPE" => "200 type set".to_string(),
+            "PWD" => format!("257 \"{}\"", self.cwd),
+            "CWD" if self.local(arg).is_dir() => {
+                self.cwd = arg.to_string();
+                "250 ok".to_string()
+            }
+            "PASV" => {
+                let l = TcpListener::bind("127.0.0.1:0").unwrap();
+                let port = l.local_addr().unwrap().port();
+                self.passive = Some(l);
+                format!(
+                    "227 Entering Passive Mode (127,0,0,1,{},{})",
+                    port >> 8,
+                    port & 0xff
+                )
+            }
+            "SIZE" | "MDTM" | "MLST" if !self.local(arg).exists() => "550 no such file".to_string(),
+            "MLSD" | "NLST" if !self.local(arg).is_dir() => "550 no such directory".to_string(),
+            "MLST" | "MLSD" if !self.behaviour.mlst => "500 unknown command".to_string(),
+            "MLST" => {
+                let m = fs::metadata(self.local(arg)).unwrap();
+                format!("250-Listing {arg}\r\n {}\r\n250 End", facts(arg, &m))
+            }
+            "MLSD" => {
+                let data = self.listing(arg, facts);
+                self.transfer(data)
+            }
+            "RETR" if !self.local(arg).is_file() => "550 no such file".to_string(),
+            "REST" => match arg.parse() {
+                Ok(n) => {
+                    self.rest = n;
+                    RESUMED.fetch_add(1, Ordering::SeqCst);
+                    format!("350 restarting at {n}")
+                }
+                Err(_) => "501 bad offset".to_string(),
+            },
+            "RETR" => {
+                let data = fs::read_to_string(self.local(arg)).unwrap();
+                let data = data[std::mem::take(&mut self.rest)..].to_string();
+                self.transfer(data)
+            }
+            "NLST" => {
+                // with the directory, as some servers give it
+                let dir = arg.trim_end_matches('/').to_string();
+                let data = self.listing(arg, |n, _| format!("{dir}/{n}"));
+                self.transfer(data)
+            }
+            "SIZE" if self.local(arg).is_dir() => "550 not a plain file".to_string(),
+            "SIZE" => format!("213 {}", fs::metadata(self.local(arg)).unwrap().len()),
+            "MDTM" => {
+                let m = fs::metadata(self.local(arg)).unwrap();
+                format!("213 {}", modify(m.modified().unwrap()))
+            }
+            "QUIT" => "221 bye".to_string(),
+            _ => "502 not implemented".to_string(),
+        }
+    }
+}
+
+fn modify(t: SystemTime) -> String {
+    DateTime::<Utc>::from(t).format("%Y%m%d%H%M%S").to_string()
+}
+
+// an MLST or MLSD line
+fn facts(name: &str, m: &fs::Metadata) -> String {
+    let kind = if m.is_dir() { "dir" } else { "file" };
+    let size = m.len();
+    let modify = modify(m.modified().unwrap());
+    format!("type={kind};size={size};modify={modify}; {name}")
+}
+
+// take one from a count of misbehaviours left, if any are
+fn take_one(left: &AtomicUsize) -> bool {
+    left.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+fn serve(stream: TcpStream, root: PathBuf, behaviour: Behaviour, left: [Arc<AtomicUsize>; 2]) {
+    let [hang_ups, cut_offs] = left;
+    let mut session = Session {
+        root,
+        cwd: "/".to_string(),
+        behaviour,
+        passive: None,
+        hang_ups,
+        cut_offs,
+        rest: 0,
+    };
+    let mut w = stream.try_clone().unwrap();
+    let r = BufReader::new(stream);
+    let _ = w.write_all(b"220 reflector test server\r\n");
+    for line in r.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+        let (cmd, arg) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+        let cmd = cmd.to_ascii_uppercase();
+        if session.hang_up(&cmd) || session.cut_off(&cmd, arg, &mut w) {
+            return;
+        }
+        let reply = session.reply(&cmd, arg);
+        if w.write_all(format!("{reply}\r\n").as_bytes()).is_err() || cmd == "QUIT" {
+            return;
+        }
+    }
+}
+
+// start a server with the given behaviour, serving a directory
+// holding pub/data.txt and pub/sub/
+fn server(behaviour: Behaviour) -> SocketAddr {
+    let root = std::env::temp_dir().join("reflector-ftp-test");
+    fs::create_dir_all(root.join("pub/sub")).unwrap();
+    fs::write(root.join("pub/data.txt"), DATA).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let hang_ups = Arc::new(AtomicUsize::new(behaviour.hang_ups));
+    let cut_offs = Arc::new(AtomicUsize::new(behaviour.cut_offs));
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let root = root.clone();
+            let left = [hang_ups.clone(), cut_offs.clone()];
+            thread::spawn(move || serve(stream, root, behaviour, left));
+        }
+    });
+    addr
+}
+
+fn mlst_server() -> SocketAddr {
+    static SERVER: OnceLock<SocketAddr> = OnceLock::new();
+    *SERVER.get_or_init(|| server(MLST))
+}
+
+fn base(addr: SocketAddr) -> Url {
+    Url::parse(&format!("ftp://{addr}/pub/")).unwrap()
+}
+
+fn client(addr: SocketAddr) -> Ftp {
+    let creds = FtpCredentials::new(USER, PASSWORD);
+    Ftp::new(&base(addr), Some(creds), FtpOptions::default())
+}
+
+fn output(name: &str) -> PathBuf {
+    let out = std::env::temp_dir().join(name);
+    let _ = fs::remove_file(&out);
+    out
+}
+
+fn data_mtime() -> SystemTime {
+    let root = std::env::temp_dir().join("reflector-ftp-test");
+    fs::metadata(root.join("pub/data.txt"))
+        .unwrap()
+        .modified()
+        .unwrap()
+}
+
+fn assert_stats(c: &mut Ftp) {
+    let s = c.stat("data.txt").unwrap();
+    assert_eq!(Some(DATA.len() as u64), s.size);
+    // to the second, as MDTM and MLST report
+    let secs = |t: SystemTime| DateTime::<Utc>::from(t).timestamp();
+    assert_eq!(Some(secs(data_mtime())), s.modified.map(secs));
+    assert!(matches!(c.stat("nothing.txt"), Err(GetError::NotFound(_))));
+    assert!(matches!(c.stat("sub"), Err(GetError::NotFound(_))));
+
+    assert!(c.exists("data.txt").unwrap());
+    assert!(!c.exists("nothing.txt").unwrap());
+    assert!(!c.exists("sub").unwrap());
+}
+
+#[test]
+fn login() {
+    let opts = RemoteOptions::new()
+        .with("user", USER)
+        .with("password", PASSWORD);
+    let mut rc = remote::from_url_with_options(&base(mlst_server()), &opts).unwrap();
+    rc.ping().unwrap();
+
+    let opts = RemoteOptions::new()
+        .with("user", USER)
+        .with("password", "wrong");
+    let mut rc = remote::from_url_with_options(&base(mlst_server()), &opts).unwrap();
+    assert!(matches!(
+        rc.ping(),
+        Err(PingError::Unreachable(ConnectError::FtpLoginErr(_)))
+    ));
+}
+
+#[test]
+fn stat_mlst() {
+    assert_stats(&mut client(mlst_server()));
+}
+
+#[test]
+fn stat_size_mdtm() {
+    let addr = server(Behaviour {
+        mlst: false,
+        ..MLST
+    });
+    assert_stats(&mut client(addr));
+}
+
+#[test]
+fn list_mlsd() {
+    let mut c = client(mlst_server());
+    let l = c.list("").unwrap();
+    assert_eq!(1, l.len(), "just the file, {l:?}");
+    assert_eq!("data.txt", l[0].name);
+    assert_eq!(Some(DATA.len() as u64), l[0].size);
+    assert!(l[0].modified.is_some());
+    assert!(c.list("x").unwrap().is_empty());
+    assert!(c.list("nothing/").unwrap().is_empty());
+    let latest = c.latest_matching("", &|n| n.ends_with(".txt")).unwrap();
+    assert_eq!("data.txt", latest.unwrap().name);
+}
+
+#[test]
+fn list_nlst() {
+    let addr = server(Behaviour {
+        mlst: false,
+        ..MLST
+    });
+    let mut c = client(addr);
+    // NLST can't tell directories from files
+    let l = c.list("s").unwrap();
+    assert_eq!(1, l.len());
+    assert_eq!("sub", l[0].name);
+    assert_eq!(None, l[0].size);
+    let l = c.list("d").unwrap();
+    assert_eq!("data.txt", l[0].name);
+    assert!(c.list("nothing/").unwrap().is_empty());
+}
+
+#[test]
+fn get() {
+    let mut c = client(mlst_server());
+    let out = output("reflector-ftp-get-test.out");
+    let got = c.get("data.txt", out.clone()).unwrap();
+    assert_eq!(DATA.len() as u64, got.size);
+    assert_eq!(DATA, fs::read_to_string(&out).unwrap());
+    fs::remove_file(&out).unwrap();
+    assert!(c.get("nothing.txt", out).is_err());
+}
+
+#[test]
+fn reconnect() {
+    let addr = server(Behaviour {
+        hang_ups: 2,
+        ..MLST
+    });
+    let mut c = client(addr);
+    c.ping().unwrap();
+    let out = output("reflector-ftp-reconnect-test.out");
+    let got = c.get("data.txt", out.clone()).unwrap();
+    assert_eq!(DATA.len() as u64, got.size);
+    assert_eq!(DATA, fs::read_to_string(&out).unwrap());
+    fs::remove_file(&out).unwrap();
+    // and the new session carries on
+    assert!(c.exists("data.txt").unwrap());
+}
+
+#[test]
+fn resume() {
+    let addr = server(Behaviour {
+        cut_offs: 2,
+        ..MLST
+    });
+    let options = FtpOptions {
+        retries: 1,
+        ..Default::default()
+    };
+    let creds = FtpCredentials::new(USER, PASSWORD);
+    let mut c = Ftp::new(&base(addr), Some(creds), options);
+    let out = output("reflector-ftp-resume-test.out");
+    let partial = out.with_file_name(".reflector-ftp-resume-test.out.partial");
+    let _ = fs::remove_file(&partial);
+    // cut off twice, resuming once, which leaves a quarter to go
+    assert!(c.get("data.txt", out.clone()).is_err());
+    assert!(!out.exists());
+    let kept = fs::read(&partial).unwrap();
+    assert_eq!(&DATA.as_bytes()[..kept.len()], &kept[..]);
+    assert_eq!(DATA.len() / 2 + DATA.len() / 4, kept.len());
+    let stamp = fs::metadata(&partial).unwrap().modified().ok();
+    assert_eq!(c.stat("data.txt").unwrap().modified, stamp);
+    let resumed = RESUMED.load(Ordering::SeqCst);
+    let got = c.get("data.txt", out.clone()).unwrap();
+    assert!(RESUMED.load(Ordering::SeqCst) > resumed);
+    assert_eq!(DATA.len() as u64, got.size);
+    assert_eq!(DATA, fs::read_to_string(&out).unwrap());
+    assert!(!partial.exists());
+    fs::remove_file(&out).unwrap();
+}
+
+#[test]
+fn retry_limit() {
+    let addr = server(Behaviour {
+        hang_ups: 2,
+        ..MLST
+    });
+    let options = FtpOptions {
+        retries: 1,
+        ..Default::default()
+    };
+    let creds = FtpCredentials::new(USER, PASSWORD);
+    let mut c = Ftp::new(&base(addr), Some(creds), options);
+    let out = output("reflector-ftp-retry-limit-test.out");
+    assert!(matches!(
+        c.get("data.txt", out.clone()),
+        Err(GetError::RetrieveFTPError(_))
+    ));
+    // with nothing left behind, not even the partial download
+    assert!(!out.exists());
+    assert!(!out
+        .with_file_name(".reflector-ftp-retry-limit-test.out.partial")
+        .exists());
+}